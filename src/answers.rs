@@ -0,0 +1,195 @@
+use crate::aoc_common::{AocDay, AocYear, DataFileType};
+use crate::config::Config;
+use crate::solution::{Answer, Part};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Config file key naming the answers file.
+pub const ANSWERS_FILE_KEY: &str = "answers_file";
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub struct AnswerKey {
+    pub year: AocYear,
+    pub day: AocDay,
+    pub part: Part,
+    pub datafile_type: DataFileType,
+}
+
+impl fmt::Display for AnswerKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {}",
+            self.year, self.day, self.part, self.datafile_type
+        )
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum AnswersError {
+    Io(PathBuf, String),
+    Syntax { line: usize, text: String },
+}
+
+impl fmt::Display for AnswersError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnswersError::Io(path, reason) => {
+                write!(f, "could not access answers {}: {}", path.display(), reason)
+            }
+            AnswersError::Syntax { line, text } => write!(
+                f,
+                "answers line {}: expected `<year> <day> <part> <data|sample> <answer>`, got {:?}",
+                line, text
+            ),
+        }
+    }
+}
+
+/// Known correct answers, one `<year> <day> <part> <data|sample> <answer>` per line.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Answers(Vec<(AnswerKey, Answer)>);
+
+fn parse_kind(s: &str) -> Option<DataFileType> {
+    match s {
+        "data" => Some(DataFileType::Data),
+        "sample" => Some(DataFileType::SampleData),
+        "debug" => Some(DataFileType::Debug),
+        _ => None,
+    }
+}
+
+fn parse_answer(s: &str) -> Answer {
+    match s.parse::<i64>() {
+        Ok(n) => Answer::Number(n),
+        Err(_) => Answer::Text(s.to_string()),
+    }
+}
+
+fn parse_entry(line: &str) -> Option<(AnswerKey, Answer)> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    match fields[..] {
+        [year, day, part, kind, ref answer @ ..] if !answer.is_empty() => {
+            let key = AnswerKey {
+                year: year.parse().ok()?,
+                day: day.parse().ok()?,
+                part: Part::try_from(part.parse::<u8>().ok()?).ok()?,
+                datafile_type: parse_kind(kind)?,
+            };
+            Some((key, parse_answer(&answer.join(" "))))
+        }
+        _ => None,
+    }
+}
+
+impl Answers {
+    pub fn parse(text: &str) -> Result<Self, AnswersError> {
+        let mut answers = Self::default();
+        for (n, line) in text.lines().enumerate() {
+            let content = line.trim();
+            if content.is_empty() || content.starts_with('#') {
+                continue;
+            }
+            let (key, answer) = parse_entry(content).ok_or_else(|| AnswersError::Syntax {
+                line: n + 1,
+                text: line.to_string(),
+            })?;
+            answers.insert(key, answer);
+        }
+        Ok(answers)
+    }
+
+    /// Loads the answers file, a missing file is an empty store.
+    pub fn load(path: &Path) -> Result<Self, AnswersError> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => Self::parse(&text),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(AnswersError::Io(path.to_path_buf(), e.to_string())),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), AnswersError> {
+        std::fs::write(path, self.to_string())
+            .map_err(|e| AnswersError::Io(path.to_path_buf(), e.to_string()))
+    }
+
+    pub fn get(&self, key: &AnswerKey) -> Option<&Answer> {
+        self.0.iter().find(|(k, _)| k == key).map(|(_, a)| a)
+    }
+
+    /// Adds or replaces an answer, keeping the store sorted.
+    pub fn insert(&mut self, key: AnswerKey, answer: Answer) {
+        match self.0.binary_search_by(|(k, _)| k.cmp(&key)) {
+            Ok(i) => self.0[i].1 = answer,
+            Err(i) => self.0.insert(i, (key, answer)),
+        }
+    }
+}
+
+impl fmt::Display for Answers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (key, answer) in &self.0 {
+            writeln!(f, "{} {}", key, answer)?;
+        }
+        Ok(())
+    }
+}
+
+/// The answers file: `--answers`, then `answers_file` in `aoc.toml`, then `answers.txt`
+/// in the repository.
+pub fn answers_path(flag: Option<PathBuf>, config: &Config) -> PathBuf {
+    flag.or_else(|| config.get_path(ANSWERS_FILE_KEY))
+        .unwrap_or_else(|| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("answers.txt"))
+}
+
+#[cfg(test)]
+mod answers_tests {
+    use super::*;
+
+    fn key(part: Part, datafile_type: DataFileType) -> AnswerKey {
+        AnswerKey {
+            year: AocYear::Aoc2020,
+            day: AocDay::Day5,
+            part,
+            datafile_type,
+        }
+    }
+
+    #[test]
+    fn test_parse_and_get() {
+        let answers =
+            Answers::parse("# known\n2020 5 1 data 998\n2020 5 2 sample EHZRFJ\n").unwrap();
+        assert_eq!(
+            answers.get(&key(Part::One, DataFileType::Data)),
+            Some(&Answer::Number(998))
+        );
+        assert_eq!(
+            answers.get(&key(Part::Two, DataFileType::SampleData)),
+            Some(&Answer::Text("EHZRFJ".to_string()))
+        );
+        assert_eq!(answers.get(&key(Part::Two, DataFileType::Data)), None);
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_lines() {
+        let result = Answers::parse("2020 5 3 data 1\n");
+        assert_eq!(
+            result,
+            Err(AnswersError::Syntax {
+                line: 1,
+                text: "2020 5 3 data 1".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_insert_keeps_order_and_round_trips() {
+        let mut answers = Answers::default();
+        answers.insert(key(Part::Two, DataFileType::Data), Answer::Number(676));
+        answers.insert(key(Part::One, DataFileType::Data), Answer::Number(1));
+        answers.insert(key(Part::One, DataFileType::Data), Answer::Number(998));
+        let text = answers.to_string();
+        assert_eq!(text, "2020 5 1 data 998\n2020 5 2 data 676\n");
+        assert_eq!(Answers::parse(&text), Ok(answers));
+    }
+}
@@ -1,4 +1,6 @@
-mod day1 {
+use crate::solution::{Answer, Solution, SolutionError};
+
+pub mod day1 {
     #[allow(dead_code)]
     pub fn prob_2numbers(v: Vec<i32>) -> Result<i32, &'static str> {
         for x in 0..v.len() - 1 {
@@ -31,12 +33,46 @@ mod day1 {
     }
 }
 
+pub struct Day1Solution;
+
+impl Solution for Day1Solution {
+    type Input = Vec<i32>;
+
+    fn parse(&self, input: &str) -> Result<Vec<i32>, SolutionError> {
+        let numbers = input
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                line.parse::<i32>()
+                    .map_err(|_| SolutionError::Parse(format!("not a number: {:?}", line)))
+            })
+            .collect::<Result<Vec<i32>, _>>()?;
+        if numbers.is_empty() {
+            return Err(SolutionError::Parse("no numbers".to_string()));
+        }
+        Ok(numbers)
+    }
+
+    fn part1(&self, input: &Vec<i32>) -> Result<Answer, SolutionError> {
+        day1::prob_2numbers(input.clone())
+            .map(Answer::from)
+            .map_err(|e| SolutionError::NoAnswer(e.to_string()))
+    }
+
+    fn part2(&self, input: &Vec<i32>) -> Result<Answer, SolutionError> {
+        day1::prob_3numbers(input.clone())
+            .map(Answer::from)
+            .map_err(|e| SolutionError::NoAnswer(e.to_string()))
+    }
+}
+
 #[cfg(test)]
 mod day1_tests {
-    use crate::aoc2020::aoc1::aocCommon::AocDay::Day1;
-    use crate::aoc2020::aoc1::aocCommon::*;
-    use crate::aoc2020::aoc1::aocCommonFile::*;
     use crate::aoc2020::day1_problems::day1::*;
+    use crate::aoc_common::file::*;
+    use crate::aoc_common::AocDay::Day1;
+    use crate::aoc_common::*;
     #[test]
     fn test_2_numbers_problem_with_sample() {
         let input = vec![1721, 979, 366, 299, 675, 1456];
@@ -55,6 +91,7 @@ mod day1_tests {
     fn test_2_numbers_problem_with_data_file() {
         fn process_file() -> Result<i32, &'static str> {
             let input: Vec<_> = load_input_lines(AocYear::Aoc2020, Day1, DataFileType::Data)
+                .unwrap_or_else(|e| panic!("{}", e))
                 .lines()
                 .map(|x| x.parse::<i32>().unwrap())
                 .collect();
@@ -67,11 +104,11 @@ mod day1_tests {
 }
 
 #[cfg(test)]
-mod day1Async_tests {
-    use crate::aoc2020::aoc1::aocCommon::AocDay::Day1;
-    use crate::aoc2020::aoc1::aocCommon::*;
-    use crate::aoc2020::aoc1::aocCommonFileAsync::*;
+mod day1_async_tests {
     use crate::aoc2020::day1_problems::day1::*;
+    use crate::aoc_common::file_async::*;
+    use crate::aoc_common::AocDay::Day1;
+    use crate::aoc_common::*;
 
     #[actix_rt::test]
     async fn test_2_numbers_problem_with_data_file_async() {
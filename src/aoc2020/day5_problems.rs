@@ -1,5 +1,7 @@
+use crate::solution::{Answer, Solution, SolutionError};
+
 #[allow(dead_code)]
-mod day5 {
+pub mod day5 {
 
     pub fn char_to_binary_digit(c: &char) -> i32 {
         match c {
@@ -13,7 +15,7 @@ mod day5 {
 
     pub fn process_line(s: Vec<char>) -> i32 {
         s.iter()
-            .map(|x| char_to_binary_digit(x))
+            .map(char_to_binary_digit)
             .fold(0, |acc, digit| (acc << 1) + digit)
     }
 
@@ -31,21 +33,48 @@ mod day5 {
             .map(|x| process_line(x.to_vec()))
             .collect::<Vec<i32>>();
 
-        for n in 8..(1 << 10) - 8 {
-            let i = n as i32;
-            if xx.contains(&(i - 1)) && !xx.contains(&i) && xx.contains(&(i + 1)) {
-                return Some(n as i32);
-            }
+        (8..(1 << 10) - 8)
+            .find(|n| xx.contains(&(n - 1)) && !xx.contains(n) && xx.contains(&(n + 1)))
+    }
+}
+
+pub struct Day5Solution;
+
+impl Solution for Day5Solution {
+    type Input = String;
+
+    fn parse(&self, input: &str) -> Result<String, SolutionError> {
+        match input
+            .lines()
+            .flat_map(str::chars)
+            .find(|c| !matches!(c, 'B' | 'F' | 'L' | 'R'))
+        {
+            Some(c) => Err(SolutionError::Parse(format!(
+                "unexpected seat code {:?}",
+                c
+            ))),
+            None => Ok(input.to_string()),
         }
-        None
+    }
+
+    fn part1(&self, input: &String) -> Result<Answer, SolutionError> {
+        day5::prob1_from_string(input)
+            .map(Answer::from)
+            .ok_or_else(|| SolutionError::NoAnswer("no seats".to_string()))
+    }
+
+    fn part2(&self, input: &String) -> Result<Answer, SolutionError> {
+        day5::prob2_from_string(input)
+            .map(Answer::from)
+            .ok_or_else(|| SolutionError::NoAnswer("no free seat".to_string()))
     }
 }
 
 #[cfg(test)]
 mod day5_tests {
-    use crate::aoc2020::aoc1::aocCommon::*;
-    use crate::aoc2020::aoc1::aocCommonFile::*;
     use crate::aoc2020::day5_problems::day5::*;
+    use crate::aoc_common::file::*;
+    use crate::aoc_common::*;
 
     #[test]
     fn test_char_with_sample() {
@@ -98,21 +127,24 @@ mod day5_tests {
 
     #[test]
     fn test_prob1_with_sample_file() {
-        let input = load_input_lines(AocYear::Aoc2020, AocDay::Day5, DataFileType::SampleData);
+        let input = load_input_lines(AocYear::Aoc2020, AocDay::Day5, DataFileType::SampleData)
+            .unwrap_or_else(|e| panic!("{}", e));
         let result = prob1_from_string(&input);
         assert_eq!(result, Some(820));
     }
 
     #[test]
     fn test_prob1_with_data_file() {
-        let input = load_input_lines(AocYear::Aoc2020, AocDay::Day5, DataFileType::Data);
+        let input = load_input_lines(AocYear::Aoc2020, AocDay::Day5, DataFileType::Data)
+            .unwrap_or_else(|e| panic!("{}", e));
         let result = prob1_from_string(&input);
         assert_eq!(result, Some(998));
     }
 
     #[test]
     fn test_prob2_with_data_file() {
-        let input = load_input_lines(AocYear::Aoc2020, AocDay::Day5, DataFileType::Data);
+        let input = load_input_lines(AocYear::Aoc2020, AocDay::Day5, DataFileType::Data)
+            .unwrap_or_else(|e| panic!("{}", e));
         let result = prob2_from_string(&input);
         assert_eq!(result, Some(676));
     }
@@ -120,13 +152,14 @@ mod day5_tests {
 
 #[cfg(test)]
 mod day5_prob2_tests {
-    use crate::aoc2020::aoc1::aocCommon::*;
-    use crate::aoc2020::aoc1::aocCommonFile::*;
     use crate::aoc2020::day5_problems::day5::*;
+    use crate::aoc_common::file::*;
+    use crate::aoc_common::*;
 
     #[test]
     fn test_prob2_with_data_file() {
-        let input = load_input_lines(AocYear::Aoc2020, AocDay::Day5, DataFileType::Data);
+        let input = load_input_lines(AocYear::Aoc2020, AocDay::Day5, DataFileType::Data)
+            .unwrap_or_else(|e| panic!("{}", e));
         let result = prob2_from_string(&input);
         assert_eq!(result, Some(676));
     }
@@ -1,5 +1,10 @@
-mod day8 {
+use crate::solution::{Answer, Solution, SolutionError};
+
+pub mod day8 {
+    use quickcheck::{Arbitrary, Gen};
+    use std::fmt;
     use std::ops::Add;
+    use std::str::FromStr;
 
     #[derive(PartialEq, Debug, Copy, Clone)]
     pub enum Instruction {
@@ -8,10 +13,106 @@ mod day8 {
         Acc(i32),
     }
 
-    impl Instruction {}
+    #[derive(PartialEq, Eq, Debug, Copy, Clone)]
+    pub enum InstructionKind {
+        Nop,
+        Jmp,
+        Acc,
+    }
+
+    impl fmt::Display for InstructionKind {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                InstructionKind::Nop => write!(f, "nop"),
+                InstructionKind::Jmp => write!(f, "jmp"),
+                InstructionKind::Acc => write!(f, "acc"),
+            }
+        }
+    }
+
+    /// The canonical text form, e.g. `nop +0` or `acc -99`.
+    impl fmt::Display for Instruction {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{} {:+}", self.kind(), self.operand())
+        }
+    }
+
+    impl Arbitrary for Instruction {
+        fn arbitrary(g: &mut Gen) -> Instruction {
+            let operand = Arbitrary::arbitrary(g);
+            match g.choose(&[0, 1, 2]) {
+                Some(0) => Instruction::Nop(operand),
+                Some(1) => Instruction::Jmp(operand),
+                _ => Instruction::Acc(operand),
+            }
+        }
+    }
+
+    impl Instruction {
+        pub fn kind(self) -> InstructionKind {
+            match self {
+                Instruction::Nop(_) => InstructionKind::Nop,
+                Instruction::Jmp(_) => InstructionKind::Jmp,
+                Instruction::Acc(_) => InstructionKind::Acc,
+            }
+        }
+
+        pub fn operand(self) -> i32 {
+            match self {
+                Instruction::Nop(v) | Instruction::Jmp(v) | Instruction::Acc(v) => v,
+            }
+        }
+    }
+
+    /// What an add does when the result leaves the `i32` range.
+    #[derive(PartialEq, Eq, Debug, Copy, Clone, Default)]
+    pub enum Overflow {
+        /// Fails with an [`OverflowError`].
+        #[default]
+        Trap,
+        Wrap,
+        Saturate,
+    }
+
+    #[derive(PartialEq, Eq, Debug, Copy, Clone)]
+    pub struct OverflowError {
+        pub value: i32,
+        pub operand: i32,
+    }
+
+    impl fmt::Display for OverflowError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{} + {} overflows i32", self.value, self.operand)
+        }
+    }
+
+    impl Overflow {
+        pub fn add(self, value: i32, operand: i32) -> Result<i32, OverflowError> {
+            match self {
+                Overflow::Trap => value
+                    .checked_add(operand)
+                    .ok_or(OverflowError { value, operand }),
+                Overflow::Wrap => Ok(value.wrapping_add(operand)),
+                Overflow::Saturate => Ok(value.saturating_add(operand)),
+            }
+        }
+    }
+
+    impl FromStr for Overflow {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "trap" => Ok(Overflow::Trap),
+                "wrap" => Ok(Overflow::Wrap),
+                "saturate" => Ok(Overflow::Saturate),
+                _ => Err(format!("unknown overflow mode {:?}", s)),
+            }
+        }
+    }
 
     #[derive(PartialEq, Debug, Eq, Hash, Copy, Clone)]
-    pub(crate) struct PcCounter {
+    pub struct PcCounter {
         v: i32,
     }
 
@@ -19,22 +120,32 @@ mod day8 {
         pub fn zero() -> Self {
             Self { v: 0 }
         }
+
+        /// The program index, `None` when the counter went negative.
+        pub fn index(self) -> Option<usize> {
+            usize::try_from(self.v).ok()
+        }
+
+        pub fn add_with(self, offset: i32, overflow: Overflow) -> Result<Self, OverflowError> {
+            overflow.add(self.v, offset).map(Self::from)
+        }
     }
     impl From<i32> for PcCounter {
         fn from(v: i32) -> Self {
             PcCounter { v }
         }
     }
-    impl From<PcCounter> for usize {
-        fn from(v: PcCounter) -> usize {
-            v.v as usize
+    impl From<PcCounter> for i32 {
+        fn from(v: PcCounter) -> i32 {
+            v.v
         }
     }
-
+    /// Panics on overflow, see [`PcCounter::add_with`] for the other modes.
     impl Add<i32> for PcCounter {
         type Output = Self;
         fn add(self, other: i32) -> Self {
-            Self { v: self.v + other }
+            self.add_with(other, Overflow::Trap)
+                .unwrap_or_else(|e| panic!("pc {}", e))
         }
     }
     #[derive(PartialEq, Debug, Copy, Clone)]
@@ -46,13 +157,31 @@ mod day8 {
         pub fn zero() -> Self {
             Self { v: 0 }
         }
+
+        pub fn add_with(self, operand: i32, overflow: Overflow) -> Result<Self, OverflowError> {
+            overflow.add(self.v, operand).map(Self::from)
+        }
     }
 
+    /// Panics on overflow, see [`AccValue::add_with`] for the other modes.
     impl Add<i32> for AccValue {
         type Output = Self;
 
         fn add(self, other: i32) -> Self {
-            Self { v: self.v + other }
+            self.add_with(other, Overflow::Trap)
+                .unwrap_or_else(|e| panic!("accumulator {}", e))
+        }
+    }
+
+    impl Default for AccValue {
+        fn default() -> Self {
+            Self::zero()
+        }
+    }
+
+    impl fmt::Display for AccValue {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{}", self.v)
         }
     }
 
@@ -66,97 +195,879 @@ mod day8 {
             AccValue { v }
         }
     }
+}
+
+pub mod day8parsing {
+    use crate::aoc2020::day8_problems::day8::*;
+    use crate::aoc_common::InputParsable;
+    use crate::machine;
+    pub use crate::machine::ProgramParseError;
+    use nom::{
+        bytes::complete::is_not,
+        character::complete::{alpha1, space1},
+        combinator::{cut, map_res, verify},
+        sequence::preceded,
+        IResult,
+    };
 
-    pub(crate) struct CpuState {
-        pub(crate) pc: PcCounter,
-        pub(crate) acc_value: AccValue,
+    /// A decimal with an optional sign, `+` included.
+    fn parse_operand(input: &str) -> IResult<&str, i32> {
+        map_res(is_not(" \t"), str::parse::<i32>)(input)
+    }
+
+    impl InputParsable for Instruction {
+        fn parse(input: &str) -> IResult<&str, Self> {
+            let (input, mnemonic) =
+                verify(alpha1, |m: &str| matches!(m, "nop" | "jmp" | "acc"))(input)?;
+            let (input, operand) = cut(preceded(space1, parse_operand))(input)?;
+            let instruction = match mnemonic {
+                "nop" => Instruction::Nop(operand),
+                "jmp" => Instruction::Jmp(operand),
+                _ => Instruction::Acc(operand),
+            };
+            Ok((input, instruction))
+        }
+    }
+
+    /// One instruction, errors are reported at line `line` (counting from 1).
+    #[allow(dead_code)]
+    pub fn parse_instruction(line: usize, s: &str) -> Result<Instruction, ProgramParseError> {
+        machine::parse_line(line, s)
+    }
+
+    /// Parses one instruction per line, blank lines are skipped.
+    pub fn parse_program(s: &str) -> Result<Vec<Instruction>, ProgramParseError> {
+        machine::parse_program(s)
     }
 }
 
-mod day8parsing {
-    use crate::aoc2020::day8_problems::day8::*;
+pub mod cpu {
+    use super::day8::{AccValue, Instruction, Overflow, PcCounter};
+    use crate::machine::{self, Flow, Machine, Vm};
+    use std::io::Write;
+
+    /// The handheld console as a [`Machine`]: one accumulator register, the pc and the
+    /// accumulator both `i32`s that overflow as configured.
+    #[derive(Debug, Default, Clone, Copy)]
+    pub struct Handheld {
+        pub overflow: Overflow,
+    }
 
-    fn parse_instruction_op(operand: i32, s: &str) -> Instruction {
-        match s {
-            "jmp" => Instruction::Jmp(operand),
-            "nop" => Instruction::Nop(operand),
-            "acc" => Instruction::Acc(operand),
-            _ => panic!("unknown instruction bang!"),
+    impl Machine for Handheld {
+        type Instruction = Instruction;
+        type Registers = AccValue;
+
+        fn execute(&self, instruction: Instruction, acc: &mut AccValue) -> Flow {
+            match instruction {
+                Instruction::Nop(_) => Flow::Next,
+                Instruction::Acc(v) => match acc.add_with(v, self.overflow) {
+                    Ok(sum) => {
+                        *acc = sum;
+                        Flow::Next
+                    }
+                    Err(_) => Flow::Overflow,
+                },
+                Instruction::Jmp(v) => Flow::Jump(v.into()),
+            }
+        }
+
+        fn advance(&self, pc: i64, offset: i64) -> Option<i64> {
+            let offset = i32::try_from(offset).ok()?;
+            let pc = PcCounter::from(i32::try_from(pc).ok()?);
+            pc.add_with(offset, self.overflow)
+                .ok()
+                .map(|pc| i32::from(pc).into())
         }
     }
 
-    pub fn parse_instruction(s: &str) -> Instruction {
-        let parts = s.split_whitespace().collect::<Vec<&str>>();
-        let sign_multiplier = if parts[1].starts_with('-') { -1 } else { 1 };
-        let operand = {
-            let numb_string = &parts[1][1..];
-            sign_multiplier * (numb_string.parse::<i32>().unwrap())
-        };
-        parse_instruction_op(operand, parts[0])
+    /// Why a [`Cpu`] stopped.
+    #[derive(PartialEq, Debug, Clone)]
+    pub enum Halt {
+        /// The program counter moved exactly one past the last instruction.
+        Terminated {
+            acc: AccValue,
+        },
+        /// The instruction at `pc` was about to run a second time.
+        InfiniteLoop {
+            pc: PcCounter,
+            acc: AccValue,
+        },
+        /// The program counter moved further past the end of the program.
+        PcOutOfRange {
+            pc: PcCounter,
+        },
+        /// The program counter moved before the first instruction.
+        NegativePc {
+            pc: PcCounter,
+        },
+        /// The instruction at `pc` overflowed the accumulator or the pc with
+        /// [`Overflow::Trap`], `acc` is the value before it.
+        Overflow {
+            pc: PcCounter,
+            acc: AccValue,
+        },
+        StepLimitReached {
+            steps: usize,
+        },
+        /// Writing the trace failed, the instruction at the pc did not run.
+        Trace(String),
+    }
+
+    pub(crate) fn pc_counter(pc: i64) -> PcCounter {
+        (pc.clamp(i32::MIN.into(), i32::MAX.into()) as i32).into()
     }
 
-    pub fn parse_program(s: &str) -> Vec<Instruction> {
-        s.lines().map(|x| parse_instruction(x)).collect()
+    /// Runs a program one instruction at a time, stopping before any instruction
+    /// would run twice.
+    pub struct Cpu<'a> {
+        vm: Vm<'a, Handheld>,
+    }
+
+    impl<'a> Cpu<'a> {
+        pub fn new(program: &'a [Instruction]) -> Self {
+            Self {
+                vm: Vm::new(program),
+            }
+        }
+
+        /// How the pc and the accumulator overflow, [`Overflow::Trap`] by default.
+        #[allow(dead_code)]
+        pub fn with_overflow(self, overflow: Overflow) -> Self {
+            Self {
+                vm: self.vm.with_machine(Handheld { overflow }),
+            }
+        }
+
+        /// Keeps an undo log, needed by [`Cpu::step_back`], [`Cpu::goto`] and the
+        /// history queries.
+        #[allow(dead_code)]
+        pub fn with_history(self) -> Self {
+            Self {
+                vm: self.vm.with_history(),
+            }
+        }
+
+        /// Stops `run` with [`Halt::StepLimitReached`] after `limit` instructions.
+        #[allow(dead_code)]
+        pub fn with_step_limit(self, limit: usize) -> Self {
+            Self {
+                vm: self.vm.with_step_limit(limit),
+            }
+        }
+
+        /// Writes `pc instruction acc_before acc_after` per executed instruction.
+        pub fn trace_to(&mut self, out: impl Write + 'a) {
+            self.vm.trace_to(out);
+        }
+
+        pub fn pc(&self) -> PcCounter {
+            pc_counter(self.vm.pc())
+        }
+
+        pub fn acc(&self) -> AccValue {
+            *self.vm.registers()
+        }
+
+        pub fn steps(&self) -> usize {
+            self.vm.state().steps
+        }
+
+        /// The instruction at the program counter, `None` outside the program.
+        pub fn current_instruction(&self) -> Option<Instruction> {
+            self.vm.current_instruction()
+        }
+
+        /// Executes one instruction, or returns why it cannot.
+        pub fn step(&mut self) -> Option<Halt> {
+            self.vm.step().map(|halt| self.halt(halt))
+        }
+
+        fn halt(&self, halt: machine::Halt) -> Halt {
+            let acc = self.acc();
+            match halt {
+                machine::Halt::Terminated => Halt::Terminated { acc },
+                machine::Halt::InfiniteLoop { pc } => Halt::InfiniteLoop {
+                    pc: pc_counter(pc),
+                    acc,
+                },
+                machine::Halt::PcOutOfRange { pc } => Halt::PcOutOfRange { pc: pc_counter(pc) },
+                machine::Halt::NegativePc { pc } => Halt::NegativePc { pc: pc_counter(pc) },
+                machine::Halt::Overflow { pc } => Halt::Overflow {
+                    pc: pc_counter(pc),
+                    acc,
+                },
+                machine::Halt::StepLimitReached { steps } => Halt::StepLimitReached { steps },
+                machine::Halt::Trace(reason) => Halt::Trace(reason),
+            }
+        }
+
+        #[allow(dead_code)]
+        pub fn run(&mut self) -> Halt {
+            loop {
+                if let Some(halt) = self.step() {
+                    return halt;
+                }
+            }
+        }
+
+        /// Undoes the last instruction, `false` at the start or without history.
+        #[allow(dead_code)]
+        pub fn step_back(&mut self) -> bool {
+            self.vm.step_back()
+        }
+
+        /// Moves to just before instruction `step` (counting from 0) runs, returning the
+        /// halt when going forwards stops first.
+        #[allow(dead_code)]
+        pub fn goto(&mut self, step: usize) -> Option<Halt> {
+            self.vm.goto(step).map(|halt| self.halt(halt))
+        }
+
+        /// The latest step whose instruction changed the accumulator.
+        #[allow(dead_code)]
+        pub fn last_acc_change(&self) -> Option<usize> {
+            self.vm.last_register_change()
+        }
+
+        /// The first step that ran the instruction at `pc`.
+        #[allow(dead_code)]
+        pub fn first_visit(&self, pc: PcCounter) -> Option<usize> {
+            self.vm.first_visit(i32::from(pc).into())
+        }
     }
 }
 
-mod part1 {
-    use super::day8::{AccValue, CpuState, Instruction, PcCounter};
-    use super::day8parsing::parse_program;
-    use std::collections::HashSet;
+pub mod debugger {
+    use super::cpu::{Cpu, Halt};
+    use super::day8::{AccValue, InstructionKind, PcCounter};
+    use std::fmt;
+    use std::io::{self, Write};
+    use std::str::FromStr;
 
-    pub(crate) fn execute_instruction(i: Instruction, state: CpuState) -> CpuState {
-        match i {
-            Instruction::Nop(_i) => CpuState {
-                pc: state.pc + 1,
-                ..state
-            },
-            Instruction::Acc(v) => CpuState {
-                pc: state.pc + 1,
-                acc_value: state.acc_value + v,
-            },
-            Instruction::Jmp(v) => CpuState {
-                pc: state.pc + v,
-                ..state
-            },
+    /// Stops execution before an instruction runs.
+    #[derive(PartialEq, Debug, Copy, Clone)]
+    pub enum Breakpoint {
+        Pc(PcCounter),
+        Kind(InstructionKind),
+    }
+
+    impl FromStr for Breakpoint {
+        type Err = String;
+
+        /// A program counter, or `nop`, `jmp` or `acc`.
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "nop" => Ok(Breakpoint::Kind(InstructionKind::Nop)),
+                "jmp" => Ok(Breakpoint::Kind(InstructionKind::Jmp)),
+                "acc" => Ok(Breakpoint::Kind(InstructionKind::Acc)),
+                pc => pc
+                    .parse::<i32>()
+                    .map(|pc| Breakpoint::Pc(pc.into()))
+                    .map_err(|_| format!("expected a pc, nop, jmp or acc, got {:?}", s)),
+            }
         }
     }
 
-    pub fn prob1(s: &str) -> AccValue {
-        fn run_cpu_until_repeat(
-            mut address_history: HashSet<PcCounter>,
-            cpu_state: CpuState,
-            program: Vec<Instruction>,
-        ) -> AccValue {
-            if address_history.contains(&cpu_state.pc) {
-                cpu_state.acc_value
-            } else {
-                address_history.insert(cpu_state.pc);
-                let program_index: usize = cpu_state.pc.into();
-                let cpu_state_dash = execute_instruction(program[program_index], cpu_state);
-                run_cpu_until_repeat(address_history, cpu_state_dash, program)
+    impl fmt::Display for Breakpoint {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Breakpoint::Pc(pc) => write!(f, "pc {}", i32::from(*pc)),
+                Breakpoint::Kind(kind) => write!(f, "{}", kind),
             }
         }
-        let prog = parse_program(s);
-        let cpu_state = CpuState {
-            pc: PcCounter::zero(),
-            acc_value: AccValue::zero(),
-        };
-        run_cpu_until_repeat(HashSet::new(), cpu_state, prog)
+    }
+
+    /// Stops execution after an instruction that makes the condition on the
+    /// accumulator true.
+    #[derive(PartialEq, Debug, Copy, Clone)]
+    pub enum Watch {
+        Changed,
+        Equals(i32),
+        Above(i32),
+        Below(i32),
+    }
+
+    impl Watch {
+        fn holds(self, acc: i32) -> bool {
+            match self {
+                Watch::Changed => false,
+                Watch::Equals(v) => acc == v,
+                Watch::Above(v) => acc > v,
+                Watch::Below(v) => acc < v,
+            }
+        }
+
+        fn triggered(self, before: AccValue, after: AccValue) -> bool {
+            let (before, after) = (i32::from(before), i32::from(after));
+            match self {
+                Watch::Changed => before != after,
+                watch => !watch.holds(before) && watch.holds(after),
+            }
+        }
+    }
+
+    impl FromStr for Watch {
+        type Err = String;
+
+        /// `changed`, `=N`, `>N` or `<N`.
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let value = |v: &str| {
+                v.parse::<i32>()
+                    .map_err(|_| format!("expected a number after the comparison, got {:?}", s))
+            };
+            match s {
+                "changed" => Ok(Watch::Changed),
+                s if s.starts_with('=') => value(&s[1..]).map(Watch::Equals),
+                s if s.starts_with('>') => value(&s[1..]).map(Watch::Above),
+                s if s.starts_with('<') => value(&s[1..]).map(Watch::Below),
+                _ => Err(format!("expected changed, =N, >N or <N, got {:?}", s)),
+            }
+        }
+    }
+
+    impl fmt::Display for Watch {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Watch::Changed => write!(f, "acc changed"),
+                Watch::Equals(v) => write!(f, "acc = {}", v),
+                Watch::Above(v) => write!(f, "acc > {}", v),
+                Watch::Below(v) => write!(f, "acc < {}", v),
+            }
+        }
+    }
+
+    #[derive(PartialEq, Debug, Clone)]
+    pub enum Stop {
+        Breakpoint(Breakpoint),
+        Watch(Watch),
+        Halted(Halt),
+    }
+
+    impl fmt::Display for Stop {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Stop::Breakpoint(b) => write!(f, "breakpoint {}", b),
+                Stop::Watch(w) => write!(f, "watch {}", w),
+                Stop::Halted(Halt::Terminated { .. }) => write!(f, "terminated"),
+                Stop::Halted(Halt::InfiniteLoop { .. }) => write!(f, "infinite loop"),
+                Stop::Halted(Halt::PcOutOfRange { .. }) => write!(f, "pc out of range"),
+                Stop::Halted(Halt::NegativePc { .. }) => write!(f, "negative pc"),
+                Stop::Halted(Halt::Overflow { .. }) => write!(f, "overflow"),
+                Stop::Halted(Halt::StepLimitReached { .. }) => write!(f, "step limit reached"),
+                Stop::Halted(Halt::Trace(reason)) => write!(f, "trace failed: {}", reason),
+            }
+        }
+    }
+
+    /// A [`Cpu`] with breakpoints and watches on the accumulator.
+    pub struct Debugger<'a> {
+        cpu: Cpu<'a>,
+        breakpoints: Vec<Breakpoint>,
+        watches: Vec<Watch>,
+    }
+
+    impl<'a> Debugger<'a> {
+        pub fn new(cpu: Cpu<'a>) -> Self {
+            Self {
+                cpu,
+                breakpoints: Vec::new(),
+                watches: Vec::new(),
+            }
+        }
+
+        pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) {
+            self.breakpoints.push(breakpoint);
+        }
+
+        pub fn add_watch(&mut self, watch: Watch) {
+            self.watches.push(watch);
+        }
+
+        /// See [`Cpu::trace_to`].
+        pub fn trace_to(&mut self, out: impl Write + 'a) {
+            self.cpu.trace_to(out);
+        }
+
+        pub fn cpu(&self) -> &Cpu<'a> {
+            &self.cpu
+        }
+
+        fn breakpoint_hit(&self) -> Option<Breakpoint> {
+            let instruction = self.cpu.current_instruction()?;
+            self.breakpoints.iter().copied().find(|b| match b {
+                Breakpoint::Pc(pc) => *pc == self.cpu.pc(),
+                Breakpoint::Kind(kind) => *kind == instruction.kind(),
+            })
+        }
+
+        /// Executes one instruction regardless of breakpoints, failing when the trace
+        /// cannot be written.
+        pub fn step(&mut self) -> io::Result<Option<Stop>> {
+            let acc_before = self.cpu.acc();
+            match self.cpu.step() {
+                Some(Halt::Trace(reason)) => return Err(io::Error::other(reason)),
+                Some(halt) => return Ok(Some(Stop::Halted(halt))),
+                None => {}
+            }
+            let acc_after = self.cpu.acc();
+            let watch = self
+                .watches
+                .iter()
+                .copied()
+                .find(|w| w.triggered(acc_before, acc_after));
+            Ok(watch.map(Stop::Watch))
+        }
+
+        /// Runs until a breakpoint, a watch or a halt; always executes at least one
+        /// instruction so resuming from a breakpoint makes progress.
+        pub fn resume(&mut self) -> io::Result<Stop> {
+            if let Some(stop) = self.step()? {
+                return Ok(stop);
+            }
+            loop {
+                if let Some(breakpoint) = self.breakpoint_hit() {
+                    return Ok(Stop::Breakpoint(breakpoint));
+                }
+                if let Some(stop) = self.step()? {
+                    return Ok(stop);
+                }
+            }
+        }
+
+        /// Like [`Debugger::resume`], but also stops on a breakpoint at the current pc.
+        pub fn start(&mut self) -> io::Result<Stop> {
+            match self.breakpoint_hit() {
+                Some(breakpoint) => Ok(Stop::Breakpoint(breakpoint)),
+                None => self.resume(),
+            }
+        }
+    }
+}
+
+pub mod control_flow {
+    use super::day8::Instruction;
+
+    /// Static view of a program: node `i` is instruction `i` and node `len` is the exit
+    /// one past the last instruction. Every instruction has at most one successor, none
+    /// when it jumps outside the program.
+    pub struct ControlFlowGraph {
+        program: Vec<Instruction>,
+        successors: Vec<Option<usize>>,
+    }
+
+    fn target(len: usize, from: usize, offset: i32) -> Option<usize> {
+        let to = from as i64 + offset as i64;
+        (0..=len as i64).contains(&to).then_some(to as usize)
+    }
+
+    fn successor(len: usize, from: usize, instruction: Instruction) -> Option<usize> {
+        match instruction {
+            Instruction::Jmp(v) => target(len, from, v),
+            Instruction::Nop(_) | Instruction::Acc(_) => target(len, from, 1),
+        }
+    }
+
+    impl ControlFlowGraph {
+        pub fn new(program: &[Instruction]) -> Self {
+            let len = program.len();
+            Self {
+                program: program.to_vec(),
+                successors: (0..len).map(|i| successor(len, i, program[i])).collect(),
+            }
+        }
+
+        /// The exit node.
+        pub fn exit(&self) -> usize {
+            self.program.len()
+        }
+
+        pub fn successor(&self, i: usize) -> Option<usize> {
+            self.successors[i]
+        }
+
+        /// Where instruction `i` would go with `jmp` and `nop` swapped, `None` for `acc`.
+        pub fn flipped_successor(&self, i: usize) -> Option<usize> {
+            match self.program[i] {
+                Instruction::Jmp(_) => target(self.exit(), i, 1),
+                Instruction::Nop(v) => target(self.exit(), i, v),
+                Instruction::Acc(_) => None,
+            }
+        }
+
+        /// Marks every node, the exit included, from which execution reaches the exit.
+        pub fn reaches_exit(&self) -> Vec<bool> {
+            let mut predecessors = vec![Vec::new(); self.exit() + 1];
+            for (i, to) in self.successors.iter().enumerate() {
+                if let Some(to) = to {
+                    predecessors[*to].push(i);
+                }
+            }
+            let mut reaches = vec![false; self.exit() + 1];
+            reaches[self.exit()] = true;
+            let mut pending = vec![self.exit()];
+            while let Some(node) = pending.pop() {
+                for &from in &predecessors[node] {
+                    if !reaches[from] {
+                        reaches[from] = true;
+                        pending.push(from);
+                    }
+                }
+            }
+            reaches
+        }
+
+        /// The instructions executed from the start, each once, in order.
+        pub fn executed_path(&self) -> Vec<usize> {
+            let mut seen = vec![false; self.exit()];
+            let mut path = Vec::new();
+            let mut node = Some(0);
+            while let Some(i) = node.filter(|&i| i < self.exit() && !seen[i]) {
+                seen[i] = true;
+                path.push(i);
+                node = self.successor(i);
+            }
+            path
+        }
+
+        /// The instruction whose flip makes the program terminate: the first one on the
+        /// executed path whose flipped successor reaches the exit.
+        pub fn repair(&self) -> Option<usize> {
+            let reaches = self.reaches_exit();
+            self.executed_path()
+                .into_iter()
+                .find(|&i| self.flipped_successor(i).is_some_and(|to| reaches[to]))
+        }
+
+        /// Instructions that no execution from the start can get to.
+        #[allow(dead_code)]
+        pub fn unreachable(&self) -> Vec<usize> {
+            let mut reached = vec![false; self.exit()];
+            let mut node = Some(0);
+            while let Some(i) = node.filter(|&i| i < self.exit() && !reached[i]) {
+                reached[i] = true;
+                node = self.successor(i);
+            }
+            (0..self.exit()).filter(|&i| !reached[i]).collect()
+        }
+
+        /// Every cycle of the graph, each starting at its lowest instruction.
+        #[allow(dead_code)]
+        pub fn loops(&self) -> Vec<Vec<usize>> {
+            // 0 unvisited, 1 on the walk being followed, 2 done
+            let mut state = vec![0u8; self.exit()];
+            let mut loops = Vec::new();
+            for start in 0..self.exit() {
+                let mut walk = Vec::new();
+                let mut node = Some(start);
+                while let Some(i) = node.filter(|&i| i < self.exit() && state[i] == 0) {
+                    state[i] = 1;
+                    walk.push(i);
+                    node = self.successor(i);
+                }
+                if let Some(i) = node.filter(|&i| i < self.exit() && state[i] == 1) {
+                    let first = walk.iter().position(|&w| w == i).expect("on the walk");
+                    let mut cycle = walk[first..].to_vec();
+                    let lowest = (0..cycle.len()).min_by_key(|&k| cycle[k]).unwrap_or(0);
+                    cycle.rotate_left(lowest);
+                    loops.push(cycle);
+                }
+                walk.iter().for_each(|&w| state[w] = 2);
+            }
+            loops
+        }
+    }
+}
+
+pub mod listing {
+    use super::day8::Instruction;
+
+    #[allow(dead_code)]
+    #[derive(PartialEq, Debug, Default, Copy, Clone)]
+    pub struct ListingOptions {
+        /// Prefix each line with its pc.
+        pub addresses: bool,
+        /// Follow each `jmp` with the pc it lands on.
+        pub jump_targets: bool,
+    }
+
+    fn jump_target(program: &[Instruction], pc: usize, offset: i32) -> String {
+        match pc as i64 + offset as i64 {
+            to if to == program.len() as i64 => "-> exit".to_string(),
+            to if (0..program.len() as i64).contains(&to) => format!("-> {}", to),
+            _ => "-> out of range".to_string(),
+        }
+    }
+
+    /// One instruction per line; without options this is text `parse_program` reads back.
+    #[allow(dead_code)]
+    pub fn format_program(program: &[Instruction], options: ListingOptions) -> String {
+        let address_width = program.len().saturating_sub(1).to_string().len();
+        let text: Vec<String> = program.iter().map(|i| i.to_string()).collect();
+        let text_width = text.iter().map(String::len).max().unwrap_or(0);
+        let mut listing = String::new();
+        for (pc, (instruction, text)) in program.iter().zip(&text).enumerate() {
+            if options.addresses {
+                listing.push_str(&format!("{:>width$}: ", pc, width = address_width));
+            }
+            match instruction {
+                Instruction::Jmp(offset) if options.jump_targets => listing.push_str(&format!(
+                    "{:<width$}  {}",
+                    text,
+                    jump_target(program, pc, *offset),
+                    width = text_width
+                )),
+                _ => listing.push_str(text),
+            }
+            listing.push('\n');
+        }
+        listing
+    }
+}
+
+/// A faster engine for the same programs: decoded once into dense opcode and operand
+/// arrays, visited instructions kept in a bitset, and buffers reused between runs.
+/// [`super::cpu::Cpu`] stays the reference it is tested against.
+pub mod fast {
+    use super::cpu::Halt;
+    use super::day8::Instruction;
+
+    const NOP: u8 = 0;
+    const JMP: u8 = 1;
+    const ACC: u8 = 2;
+
+    /// A fixed size set of instruction indices.
+    #[derive(Debug, Default, Clone)]
+    pub struct BitSet {
+        words: Vec<u64>,
+    }
+
+    impl BitSet {
+        /// Empties the set and sizes it for indices below `len`.
+        pub fn reset(&mut self, len: usize) {
+            self.words.clear();
+            self.words.resize(len.div_ceil(64), 0);
+        }
+
+        /// Adds `i`, returning `false` when it was already present.
+        pub fn insert(&mut self, i: usize) -> bool {
+            let (word, bit) = (i / 64, 1u64 << (i % 64));
+            let added = self.words[word] & bit == 0;
+            self.words[word] |= bit;
+            added
+        }
+
+        #[allow(dead_code)]
+        pub fn contains(&self, i: usize) -> bool {
+            self.words
+                .get(i / 64)
+                .is_some_and(|word| word & (1u64 << (i % 64)) != 0)
+        }
+    }
+
+    #[derive(Debug, Default, Clone)]
+    pub struct FastCpu {
+        ops: Vec<u8>,
+        args: Vec<i32>,
+        visited: BitSet,
+    }
+
+    impl FastCpu {
+        pub fn new(program: &[Instruction]) -> Self {
+            let mut cpu = Self::default();
+            cpu.load(program);
+            cpu
+        }
+
+        /// Decodes `program` into the existing buffers.
+        pub fn load(&mut self, program: &[Instruction]) {
+            self.ops.clear();
+            self.args.clear();
+            for instruction in program {
+                let (op, arg) = match *instruction {
+                    Instruction::Nop(v) => (NOP, v),
+                    Instruction::Jmp(v) => (JMP, v),
+                    Instruction::Acc(v) => (ACC, v),
+                };
+                self.ops.push(op);
+                self.args.push(arg);
+            }
+        }
+
+        /// Runs from pc 0 with a zero accumulator, halting like [`super::cpu::Cpu::run`]
+        /// with [`super::day8::Overflow::Trap`].
+        pub fn run(&mut self) -> Halt {
+            self.run_flipped(None)
+        }
+
+        /// Runs as if the `jmp`/`nop` at `flip` were swapped, leaving the decoded
+        /// program as it is.
+        pub fn run_flipped(&mut self, flip: Option<usize>) -> Halt {
+            let len = self.ops.len();
+            self.visited.reset(len);
+            let mut pc: i32 = 0;
+            let mut acc: i32 = 0;
+            loop {
+                let i = match usize::try_from(pc) {
+                    Ok(i) if i == len => return Halt::Terminated { acc: acc.into() },
+                    Ok(i) if i < len => i,
+                    Ok(_) => return Halt::PcOutOfRange { pc: pc.into() },
+                    Err(_) => return Halt::NegativePc { pc: pc.into() },
+                };
+                if !self.visited.insert(i) {
+                    return Halt::InfiniteLoop {
+                        pc: pc.into(),
+                        acc: acc.into(),
+                    };
+                }
+                let op = match self.ops[i] {
+                    NOP if flip == Some(i) => JMP,
+                    JMP if flip == Some(i) => NOP,
+                    op => op,
+                };
+                let next = match op {
+                    ACC => match acc.checked_add(self.args[i]) {
+                        Some(sum) => {
+                            acc = sum;
+                            pc.checked_add(1)
+                        }
+                        None => None,
+                    },
+                    JMP => pc.checked_add(self.args[i]),
+                    _ => pc.checked_add(1),
+                };
+                match next {
+                    Some(next) => pc = next,
+                    None => {
+                        return Halt::Overflow {
+                            pc: pc.into(),
+                            acc: acc.into(),
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub mod part1 {
+    use super::cpu::Halt;
+    use super::day8::{AccValue, Instruction};
+    use super::day8parsing::parse_program;
+    use super::fast::FastCpu;
+
+    #[allow(dead_code)]
+    pub fn prob1(s: &str) -> Option<AccValue> {
+        acc_at_first_repeat(&parse_program(s).ok()?)
+    }
+
+    /// The accumulator just before any instruction runs a second time, `None` when the
+    /// program halts some other way.
+    pub fn acc_at_first_repeat(prog: &[Instruction]) -> Option<AccValue> {
+        match FastCpu::new(prog).run() {
+            Halt::InfiniteLoop { acc, .. } => Some(acc),
+            _ => None,
+        }
+    }
+}
+
+pub mod part2 {
+    use super::control_flow::ControlFlowGraph;
+    use super::cpu::Halt;
+    use super::day8::{AccValue, Instruction};
+    use super::day8parsing::parse_program;
+    use super::fast::FastCpu;
+
+    /// The accumulator when the program steps exactly one past its last instruction,
+    /// `None` when it loops or jumps anywhere else outside the program.
+    pub fn acc_at_termination(prog: &[Instruction]) -> Option<AccValue> {
+        match FastCpu::new(prog).run() {
+            Halt::Terminated { acc } => Some(acc),
+            _ => None,
+        }
+    }
+
+    pub fn flip(i: Instruction) -> Option<Instruction> {
+        match i {
+            Instruction::Jmp(v) => Some(Instruction::Nop(v)),
+            Instruction::Nop(v) => Some(Instruction::Jmp(v)),
+            Instruction::Acc(_) => None,
+        }
+    }
+
+    /// Tries swapping each `jmp`/`nop` in turn and returns the accumulator of the first
+    /// repaired program that terminates.
+    #[allow(dead_code)]
+    pub fn acc_after_repair(prog: &[Instruction]) -> Option<AccValue> {
+        let mut cpu = FastCpu::new(prog);
+        (0..prog.len())
+            .filter(|&i| flip(prog[i]).is_some())
+            .find_map(|i| match cpu.run_flipped(Some(i)) {
+                Halt::Terminated { acc } => Some(acc),
+                _ => None,
+            })
+    }
+
+    /// Like [`acc_after_repair`] but finds the instruction to flip with the control-flow
+    /// graph, so the program only runs once.
+    pub fn acc_after_repair_linear(prog: &[Instruction]) -> Option<AccValue> {
+        let i = ControlFlowGraph::new(prog).repair()?;
+        let mut patched = prog.to_vec();
+        patched[i] = flip(prog[i])?;
+        acc_at_termination(&patched)
+    }
+
+    #[allow(dead_code)]
+    pub fn prob2(s: &str) -> Option<AccValue> {
+        acc_after_repair_linear(&parse_program(s).ok()?)
+    }
+}
+
+pub struct Day8Solution;
+
+impl Solution for Day8Solution {
+    type Input = Vec<day8::Instruction>;
+
+    fn parse(&self, input: &str) -> Result<Vec<day8::Instruction>, SolutionError> {
+        day8parsing::parse_program(input).map_err(|e| SolutionError::Parse(e.to_string()))
+    }
+
+    fn part1(&self, input: &Vec<day8::Instruction>) -> Result<Answer, SolutionError> {
+        part1::acc_at_first_repeat(input)
+            .map(|acc| i32::from(acc).into())
+            .ok_or_else(|| SolutionError::NoAnswer("the program does not loop".to_string()))
+    }
+
+    fn part2(&self, input: &Vec<day8::Instruction>) -> Result<Answer, SolutionError> {
+        part2::acc_after_repair_linear(input)
+            .map(|acc| i32::from(acc).into())
+            .ok_or_else(|| SolutionError::NoAnswer("no single swap terminates".to_string()))
     }
 }
 
 #[cfg(test)]
 mod day8_tests {
+    use super::control_flow::*;
+    use super::cpu::*;
     use super::day8::*;
-    use super::day8parsing::parse_instruction;
+    use super::day8parsing::{parse_instruction, parse_program, ProgramParseError};
+    use super::debugger::*;
+    use super::fast::{BitSet, FastCpu};
+    use super::listing::*;
     use super::part1::*;
+    use super::part2::*;
+    use crate::aoc_common::file::load_input_lines;
+    use crate::aoc_common::{AocDay, AocYear, DataFileType};
+
+    fn load(datafile_type: DataFileType) -> String {
+        load_input_lines(AocYear::Aoc2020, AocDay::Day8, datafile_type)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
 
     #[test]
     fn test_char_with_sample_line2() {
         let input = "nop +0";
-        let result = parse_instruction(input);
-        assert_eq!(result, Instruction::Nop(0));
+        let result = parse_instruction(1, input);
+        assert_eq!(result, Ok(Instruction::Nop(0)));
     }
 
     #[test]
@@ -178,6 +1089,546 @@ mod day8_tests {
     jmp -4\n\
     acc +6\n";
         let result = prob1(input);
-        assert_eq!(result, 5.into());
+        assert_eq!(result, Some(5.into()));
+    }
+
+    #[test]
+    fn test_sample_program_part2() {
+        let result = prob2(&load(DataFileType::SampleData));
+        assert_eq!(result, Some(8.into()));
+    }
+
+    #[test]
+    fn test_sample_program_part1_from_file() {
+        let result = prob1(&load(DataFileType::SampleData));
+        assert_eq!(result, Some(5.into()));
+    }
+
+    #[test]
+    fn test_termination_needs_exact_end() {
+        use Instruction::*;
+        assert_eq!(acc_at_termination(&[Acc(2), Nop(0)]), Some(2.into()));
+        assert_eq!(acc_at_termination(&[Acc(2), Jmp(2)]), None);
+        assert_eq!(acc_at_termination(&[Jmp(-1)]), None);
+        assert_eq!(acc_at_termination(&[Jmp(0)]), None);
+    }
+
+    #[test]
+    fn test_repair_flips_only_jmp_and_nop() {
+        use Instruction::*;
+        assert_eq!(acc_after_repair(&[Acc(1), Jmp(-1)]), Some(1.into()));
+        assert_eq!(acc_after_repair(&[Nop(0), Acc(3), Jmp(-2)]), Some(3.into()));
+        assert_eq!(acc_after_repair(&[Acc(1), Jmp(0), Jmp(-1)]), None);
+    }
+
+    #[test]
+    fn test_cpu_halt_reasons() {
+        use Instruction::*;
+        assert_eq!(
+            Cpu::new(&[Acc(2), Nop(0)]).run(),
+            Halt::Terminated { acc: 2.into() }
+        );
+        assert_eq!(
+            Cpu::new(&[Acc(2), Jmp(-1)]).run(),
+            Halt::InfiniteLoop {
+                pc: 0.into(),
+                acc: 2.into()
+            }
+        );
+        assert_eq!(
+            Cpu::new(&[Jmp(-1)]).run(),
+            Halt::NegativePc { pc: (-1).into() }
+        );
+        assert_eq!(
+            Cpu::new(&[Jmp(3)]).run(),
+            Halt::PcOutOfRange { pc: 3.into() }
+        );
+        assert_eq!(Cpu::new(&[]).run(), Halt::Terminated { acc: 0.into() });
+    }
+
+    #[test]
+    fn test_cpu_overflow_modes() {
+        use Instruction::*;
+        let program = [Acc(i32::MAX), Acc(1)];
+        assert_eq!(
+            Cpu::new(&program).run(),
+            Halt::Overflow {
+                pc: 1.into(),
+                acc: i32::MAX.into()
+            }
+        );
+        assert_eq!(
+            Cpu::new(&program).with_overflow(Overflow::Wrap).run(),
+            Halt::Terminated {
+                acc: i32::MIN.into()
+            }
+        );
+        assert_eq!(
+            Cpu::new(&program).with_overflow(Overflow::Saturate).run(),
+            Halt::Terminated {
+                acc: i32::MAX.into()
+            }
+        );
+        let pc = PcCounter::from(i32::MAX - 1);
+        assert_eq!(
+            pc.add_with(2, Overflow::Trap),
+            Err(OverflowError {
+                value: i32::MAX - 1,
+                operand: 2
+            })
+        );
+        assert_eq!(pc.add_with(2, Overflow::Wrap), Ok(i32::MIN.into()));
+        assert_eq!(pc.add_with(2, Overflow::Saturate), Ok(i32::MAX.into()));
+        assert_eq!("saturate".parse(), Ok(Overflow::Saturate));
+        assert!("clamp".parse::<Overflow>().is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "accumulator 2147483647 + 1 overflows i32")]
+    fn test_acc_add_panics_on_overflow() {
+        let _ = AccValue::from(i32::MAX) + 1;
+    }
+
+    #[test]
+    fn test_cpu_history() {
+        let program = sample_program();
+        let mut cpu = Cpu::new(&program).with_history();
+        let halt = cpu.run();
+        assert_eq!(
+            halt,
+            Halt::InfiniteLoop {
+                pc: 1.into(),
+                acc: 5.into()
+            }
+        );
+        assert_eq!(cpu.steps(), 7);
+        assert_eq!(cpu.last_acc_change(), Some(5));
+        assert_eq!(cpu.first_visit(6.into()), Some(3));
+        assert_eq!(cpu.first_visit(5.into()), None);
+
+        assert!(cpu.step_back());
+        assert_eq!((cpu.pc(), cpu.acc(), cpu.steps()), (4.into(), 5.into(), 6));
+        assert_eq!(cpu.goto(2), None);
+        assert_eq!((cpu.pc(), cpu.acc()), (2.into(), 1.into()));
+        assert_eq!(cpu.last_acc_change(), Some(1));
+        assert_eq!(cpu.run(), halt);
+        while cpu.step_back() {}
+        assert_eq!((cpu.pc(), cpu.acc(), cpu.steps()), (0.into(), 0.into(), 0));
+        assert_eq!(cpu.goto(100), Some(halt));
+    }
+
+    #[test]
+    fn test_cpu_step_and_limit() {
+        use Instruction::*;
+        let program = [Acc(1), Acc(2), Acc(3)];
+        let mut cpu = Cpu::new(&program).with_step_limit(2);
+        assert_eq!(cpu.step(), None);
+        assert_eq!((cpu.pc(), cpu.acc(), cpu.steps()), (1.into(), 1.into(), 1));
+        assert_eq!(cpu.run(), Halt::StepLimitReached { steps: 2 });
+        assert_eq!(cpu.acc(), 3.into());
+    }
+
+    #[test]
+    fn test_cpu_runs_long_programs() {
+        let mut program = vec![Instruction::Acc(1); 1_000_000];
+        program.push(Instruction::Jmp(-1_000_000));
+        assert_eq!(
+            Cpu::new(&program).run(),
+            Halt::InfiniteLoop {
+                pc: 0.into(),
+                acc: 1_000_000.into()
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_operand_signs() {
+        assert_eq!(parse_instruction(1, "acc 7"), Ok(Instruction::Acc(7)));
+        assert_eq!(parse_instruction(1, "acc +7"), Ok(Instruction::Acc(7)));
+        assert_eq!(parse_instruction(1, "  jmp -3  "), Ok(Instruction::Jmp(-3)));
+    }
+
+    #[test]
+    fn test_parse_program_reports_position() {
+        let error = |line, column, token: &str| {
+            Err(ProgramParseError {
+                line,
+                column,
+                token: token.to_string(),
+            })
+        };
+        assert_eq!(parse_program("nop +0\nbang +1\n"), error(2, 1, "bang"));
+        assert_eq!(parse_program("nop +0\n\nnop"), error(3, 4, ""));
+        assert_eq!(parse_program("acc +x1"), error(1, 5, "+x1"));
+        assert_eq!(parse_program("acc 99999999999"), error(1, 5, "99999999999"));
+        assert_eq!(parse_program("jmp +1 +2"), error(1, 8, "+2"));
+        assert_eq!(
+            parse_instruction(7, "bang +1").unwrap_err(),
+            error(7, 1, "bang").unwrap_err()
+        );
+        assert_eq!(
+            error(2, 1, "bang").unwrap_err().to_string(),
+            "line 2, column 1: unexpected \"bang\""
+        );
+        assert_eq!(
+            error(3, 4, "").unwrap_err().to_string(),
+            "line 3, column 4: unexpected end of line"
+        );
+    }
+
+    fn sample_program() -> Vec<Instruction> {
+        parse_program(&load(DataFileType::SampleData)).unwrap()
+    }
+
+    #[test]
+    fn test_debugger_breakpoints_and_step() {
+        let program = sample_program();
+        let mut debugger = Debugger::new(Cpu::new(&program));
+        debugger.add_breakpoint("6".parse().unwrap());
+        debugger.add_breakpoint("jmp".parse().unwrap());
+        assert_eq!(
+            debugger.start().unwrap(),
+            Stop::Breakpoint(Breakpoint::Kind(InstructionKind::Jmp))
+        );
+        assert_eq!(debugger.cpu().pc(), 2.into());
+        assert_eq!(
+            debugger.resume().unwrap(),
+            Stop::Breakpoint(Breakpoint::Pc(6.into()))
+        );
+        // stepping onto the jmp at 7 does not stop there again when resuming
+        assert_eq!(debugger.step().unwrap(), None);
+        assert_eq!(debugger.cpu().pc(), 7.into());
+        assert_eq!(
+            debugger.resume().unwrap(),
+            Stop::Breakpoint(Breakpoint::Kind(InstructionKind::Jmp))
+        );
+        assert_eq!(debugger.cpu().pc(), 4.into());
+    }
+
+    #[test]
+    fn test_debugger_watches() {
+        let program = sample_program();
+        let mut debugger = Debugger::new(Cpu::new(&program));
+        debugger.add_watch("=2".parse().unwrap());
+        assert_eq!(debugger.start().unwrap(), Stop::Watch(Watch::Equals(2)));
+        assert_eq!(debugger.cpu().acc(), 2.into());
+        assert_eq!(
+            debugger.resume().unwrap(),
+            Stop::Halted(Halt::InfiniteLoop {
+                pc: 1.into(),
+                acc: 5.into()
+            })
+        );
+
+        let mut debugger = Debugger::new(Cpu::new(&program));
+        debugger.add_watch(Watch::Changed);
+        assert_eq!(debugger.start().unwrap(), Stop::Watch(Watch::Changed));
+        assert_eq!(debugger.cpu().steps(), 2);
+        assert_eq!("<0".parse(), Ok(Watch::Below(0)));
+        assert!("acc".parse::<Watch>().is_err());
+        assert!("jmp2".parse::<Breakpoint>().is_err());
+    }
+
+    #[test]
+    fn test_debugger_trace() {
+        let program = sample_program();
+        let mut trace = Vec::new();
+        let mut debugger = Debugger::new(Cpu::new(&program));
+        debugger.trace_to(&mut trace);
+        assert!(matches!(debugger.start(), Ok(Stop::Halted(_))));
+        drop(debugger);
+        let trace = String::from_utf8(trace).unwrap();
+        assert_eq!(trace.lines().count(), 7);
+        assert!(trace.starts_with("0 nop +0 0 0\n1 acc +1 0 1\n2 jmp +4 1 1\n"));
+        assert!(trace.ends_with("4 jmp -3 5 5\n"));
+    }
+
+    /// A trace destination that always fails.
+    struct Broken;
+
+    impl std::io::Write for Broken {
+        fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::other("disk full"))
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_failed_trace_stops() {
+        let program = sample_program();
+        let mut cpu = Cpu::new(&program);
+        cpu.trace_to(Broken);
+        assert_eq!(cpu.run(), Halt::Trace("disk full".to_string()));
+        assert_eq!((cpu.pc(), cpu.steps()), (0.into(), 0));
+
+        let mut debugger = Debugger::new(Cpu::new(&program));
+        debugger.trace_to(Broken);
+        let error = debugger.start().unwrap_err();
+        assert_eq!(error.to_string(), "disk full");
+    }
+
+    #[test]
+    fn test_control_flow_sample() {
+        let graph = ControlFlowGraph::new(&sample_program());
+        assert_eq!(graph.executed_path(), vec![0, 1, 2, 6, 7, 3, 4]);
+        assert_eq!(graph.repair(), Some(7));
+        assert_eq!(graph.unreachable(), vec![5, 8]);
+        assert_eq!(graph.loops(), vec![vec![1, 2, 6, 7, 3, 4]]);
+        let reaches = graph.reaches_exit();
+        assert_eq!(
+            (0..=9).filter(|&i| reaches[i]).collect::<Vec<_>>(),
+            vec![8, 9]
+        );
+    }
+
+    #[test]
+    fn test_control_flow_out_of_range_and_several_loops() {
+        use Instruction::*;
+        let graph = ControlFlowGraph::new(&[Jmp(2), Jmp(-5), Jmp(0), Nop(0), Jmp(-1)]);
+        assert_eq!(graph.successor(1), None);
+        assert_eq!(graph.loops(), vec![vec![2], vec![3, 4]]);
+        assert_eq!(graph.unreachable(), vec![1, 3, 4]);
+        assert_eq!(graph.flipped_successor(2), Some(3));
+        assert_eq!(graph.repair(), None);
+    }
+
+    #[test]
+    fn test_bitset() {
+        let mut set = BitSet::default();
+        set.reset(130);
+        assert!(set.insert(0));
+        assert!(set.insert(129));
+        assert!(!set.insert(129));
+        assert!(set.contains(0) && set.contains(129) && !set.contains(64));
+        set.reset(130);
+        assert!(!set.contains(129));
+    }
+
+    #[test]
+    fn test_fast_cpu_matches_cpu() {
+        use Instruction::*;
+        let programs = [
+            sample_program(),
+            vec![Nop(0), Acc(3), Jmp(-2)],
+            vec![Acc(1), Jmp(-5)],
+            vec![Jmp(3), Acc(1)],
+            Vec::new(),
+        ];
+        let mut fast = FastCpu::default();
+        for program in &programs {
+            fast.load(program);
+            assert_eq!(fast.run(), Cpu::new(program).run(), "{:?}", program);
+        }
+    }
+
+    #[test]
+    fn test_linear_repair_matches_brute_force() {
+        use Instruction::*;
+        let programs = [
+            sample_program(),
+            vec![Nop(0), Acc(3), Jmp(-2)],
+            vec![Jmp(2), Acc(1), Nop(-2), Acc(5)],
+            vec![Acc(1), Jmp(0), Jmp(-1)],
+            vec![Nop(3), Jmp(0), Acc(7), Jmp(-1), Acc(2)],
+        ];
+        for program in &programs {
+            assert_eq!(
+                acc_after_repair_linear(program),
+                acc_after_repair(program),
+                "{:?}",
+                program
+            );
+        }
+    }
+
+    #[test]
+    fn test_instruction_display() {
+        assert_eq!(Instruction::Nop(0).to_string(), "nop +0");
+        assert_eq!(Instruction::Acc(-99).to_string(), "acc -99");
+        assert_eq!(Instruction::Jmp(4).to_string(), "jmp +4");
+    }
+
+    #[test]
+    fn test_listing_annotations() {
+        use Instruction::*;
+        let program = [
+            Nop(0),
+            Acc(-12),
+            Jmp(-2),
+            Jmp(1),
+            Jmp(5),
+            Acc(1),
+            Acc(1),
+            Acc(1),
+            Acc(1),
+            Acc(1),
+            Jmp(-1),
+        ];
+        let options = ListingOptions {
+            addresses: true,
+            jump_targets: true,
+        };
+        let listing = format_program(&program, options);
+        let lines: Vec<_> = listing.lines().collect();
+        assert_eq!(lines[0], " 0: nop +0");
+        assert_eq!(lines[2], " 2: jmp -2   -> 0");
+        assert_eq!(lines[3], " 3: jmp +1   -> 4");
+        assert_eq!(lines[4], " 4: jmp +5   -> 9");
+        assert_eq!(lines[10], "10: jmp -1   -> 9");
+        let program = [Jmp(1), Jmp(-2)];
+        assert_eq!(
+            format_program(
+                &program,
+                ListingOptions {
+                    addresses: false,
+                    jump_targets: true
+                }
+            ),
+            "jmp +1  -> 1\njmp -2  -> out of range\n"
+        );
+        assert_eq!(
+            format_program(
+                &[Jmp(1)],
+                ListingOptions {
+                    addresses: false,
+                    jump_targets: true
+                }
+            ),
+            "jmp +1  -> exit\n"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests_quickcheck {
+    use super::cpu::{Cpu, Halt};
+    use super::day8::*;
+    use super::day8parsing::{parse_instruction, parse_program};
+    use super::fast::FastCpu;
+    use super::listing::{format_program, ListingOptions};
+    use quickcheck_macros::quickcheck;
+
+    #[quickcheck]
+    fn test_instruction_round_trip(instruction: Instruction) -> bool {
+        parse_instruction(1, &instruction.to_string()) == Ok(instruction)
+    }
+
+    #[quickcheck]
+    fn test_program_round_trip(program: Vec<Instruction>) -> bool {
+        let text = format_program(&program, ListingOptions::default());
+        parse_program(&text) == Ok(program)
+    }
+
+    #[quickcheck]
+    fn test_listing_has_a_line_per_instruction(program: Vec<Instruction>) -> bool {
+        let options = ListingOptions {
+            addresses: true,
+            jump_targets: true,
+        };
+        let listing = format_program(&program, options);
+        listing.lines().count() == program.len()
+            && listing
+                .lines()
+                .enumerate()
+                .all(|(pc, line)| line.trim_start().starts_with(&format!("{}: ", pc)))
+    }
+
+    #[quickcheck]
+    fn test_fast_cpu_agrees_with_cpu(program: Vec<Instruction>) -> bool {
+        // small operands keep jumps inside the program and the accumulator from overflowing
+        let program: Vec<_> = program
+            .into_iter()
+            .map(|i| match i {
+                Instruction::Nop(v) => Instruction::Nop(v % 8),
+                Instruction::Jmp(v) => Instruction::Jmp(v % 8),
+                Instruction::Acc(v) => Instruction::Acc(v % 1000),
+            })
+            .collect();
+        let expected = Cpu::new(&program).run();
+        FastCpu::new(&program).run() == expected
+    }
+
+    #[quickcheck]
+    fn test_overflow_modes_match_std(a: i32, b: i32) -> bool {
+        Overflow::Trap.add(a, b).ok() == a.checked_add(b)
+            && Overflow::Wrap.add(a, b) == Ok(a.wrapping_add(b))
+            && Overflow::Saturate.add(a, b) == Ok(a.saturating_add(b))
+    }
+
+    #[quickcheck]
+    fn test_pc_index_only_when_not_negative(v: i32) -> bool {
+        PcCounter::from(v).index() == usize::try_from(v).ok()
+    }
+
+    #[quickcheck]
+    fn test_acc_overflow_halts(a: i32, b: i32) -> bool {
+        let program = [Instruction::Acc(a), Instruction::Acc(b)];
+        let run = |overflow| Cpu::new(&program).with_overflow(overflow).run();
+        let trapped = match a.checked_add(b) {
+            Some(acc) => Halt::Terminated { acc: acc.into() },
+            None => Halt::Overflow {
+                pc: 1.into(),
+                acc: a.into(),
+            },
+        };
+        run(Overflow::Trap) == trapped
+            && FastCpu::new(&program).run() == trapped
+            && run(Overflow::Wrap)
+                == Halt::Terminated {
+                    acc: a.wrapping_add(b).into(),
+                }
+            && run(Overflow::Saturate)
+                == Halt::Terminated {
+                    acc: a.saturating_add(b).into(),
+                }
+    }
+
+    #[quickcheck]
+    fn test_jump_halts(nops: u8, offset: i32) -> bool {
+        let mut program = vec![Instruction::Nop(0); nops.into()];
+        program.push(Instruction::Jmp(offset));
+        let pc = i32::from(nops);
+        let expected = match i64::from(pc) + i64::from(offset) {
+            target if target > i32::MAX.into() => Halt::Overflow {
+                pc: pc.into(),
+                acc: 0.into(),
+            },
+            target if target < 0 => Halt::NegativePc {
+                pc: (target as i32).into(),
+            },
+            target if target <= pc.into() => Halt::InfiniteLoop {
+                pc: (target as i32).into(),
+                acc: 0.into(),
+            },
+            target if target == i64::from(pc) + 1 => Halt::Terminated { acc: 0.into() },
+            target => Halt::PcOutOfRange {
+                pc: (target as i32).into(),
+            },
+        };
+        Cpu::new(&program).run() == expected && FastCpu::new(&program).run() == expected
+    }
+
+    #[quickcheck]
+    fn test_goto_matches_replay(program: Vec<Instruction>, step: u8) -> bool {
+        let program: Vec<_> = program
+            .into_iter()
+            .map(|i| match i {
+                Instruction::Jmp(v) => Instruction::Jmp(v % 8),
+                i => i,
+            })
+            .collect();
+        let step = usize::from(step) % (program.len() + 1);
+        let mut cpu = Cpu::new(&program)
+            .with_overflow(Overflow::Wrap)
+            .with_history();
+        cpu.run();
+        cpu.goto(step);
+        let mut replay = Cpu::new(&program).with_overflow(Overflow::Wrap);
+        replay.goto(step);
+        (cpu.pc(), cpu.acc(), cpu.steps()) == (replay.pc(), replay.acc(), replay.steps())
+            && cpu.run() == replay.run()
     }
 }
@@ -1,21 +1,20 @@
+use crate::solution::{Answer, Solution, SolutionError};
+
+#[allow(dead_code)]
 mod day11 {
     use nom::{
         branch::alt,
         bytes::complete::tag,
         character::complete::{char, digit1, line_ending, multispace1, space1},
-        combinator::{map, map_res},
-        multi::{many1, separated_list0},
-        sequence::{preceded, terminated, tuple},
+        combinator::{map, map_res, value},
+        multi::{many0, many1, separated_list0},
+        sequence::{delimited, preceded, terminated, tuple},
         IResult,
     };
     use quickcheck::{Arbitrary, Gen};
     use std::fmt;
 
-    pub trait InputParsable {
-        fn parse(input: &str) -> IResult<&str, Self>
-        where
-            Self: Sized;
-    }
+    pub use crate::aoc_common::InputParsable;
 
     #[derive(Debug, PartialEq, Clone)]
     pub struct MonkeyNumber(pub u64);
@@ -23,10 +22,35 @@ mod day11 {
     #[derive(Debug, PartialEq, Clone)]
     pub struct StartingItems(pub Vec<u64>);
 
+    /// `old` or a number.
+    #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+    pub enum Operand {
+        Old,
+        Literal(u64),
+    }
+
+    #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+    pub enum BinOp {
+        Add,
+        Sub,
+        Mul,
+        Div,
+    }
+
+    /// A monkey's operation, the right hand side of `new = ...`. `*` and `/` bind
+    /// tighter than `+` and `-`, otherwise evaluation is left to right unless
+    /// parenthesised.
     #[derive(Debug, PartialEq, Clone)]
-    pub enum Operator {
-        Plus(u64),
-        Multiply(u64),
+    pub enum Expression {
+        Operand(Operand),
+        Binary(Box<Expression>, BinOp, Box<Expression>),
+    }
+
+    #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+    pub enum EvalError {
+        /// The result does not fit a `u64`, below zero included.
+        Overflow,
+        DivideByZero,
     }
     impl Arbitrary for Monkey {
         fn arbitrary(g: &mut Gen) -> Monkey {
@@ -50,17 +74,37 @@ mod day11 {
         }
     }
 
-    impl Arbitrary for Operator {
-        fn arbitrary(g: &mut Gen) -> Operator {
-            // Assuming Operator is an enum with variants Plus and Minus
+    impl Arbitrary for Operand {
+        fn arbitrary(g: &mut Gen) -> Operand {
             if Arbitrary::arbitrary(g) {
-                Operator::Plus(Arbitrary::arbitrary(g))
+                Operand::Old
             } else {
-                Operator::Multiply(Arbitrary::arbitrary(g))
+                Operand::Literal(Arbitrary::arbitrary(g))
             }
         }
     }
 
+    impl Arbitrary for BinOp {
+        fn arbitrary(g: &mut Gen) -> BinOp {
+            *g.choose(&[BinOp::Add, BinOp::Sub, BinOp::Mul, BinOp::Div])
+                .unwrap()
+        }
+    }
+
+    impl Arbitrary for Expression {
+        fn arbitrary(g: &mut Gen) -> Expression {
+            fn tree(g: &mut Gen, depth: usize) -> Expression {
+                if depth == 0 || bool::arbitrary(g) {
+                    Operand::arbitrary(g).into()
+                } else {
+                    let lhs = tree(g, depth - 1);
+                    Expression::binary(lhs, BinOp::arbitrary(g), tree(g, depth - 1))
+                }
+            }
+            tree(g, 4)
+        }
+    }
+
     impl Arbitrary for MonkeyTest {
         fn arbitrary(g: &mut Gen) -> MonkeyTest {
             MonkeyTest {
@@ -82,7 +126,7 @@ mod day11 {
     pub struct Monkey {
         pub number: MonkeyNumber,
         pub starting_items: StartingItems,
-        pub operation: Operator,
+        pub operation: Expression,
         pub test: MonkeyTest,
     }
     impl fmt::Display for Monkey {
@@ -102,18 +146,13 @@ mod day11 {
                 .map(|f| (*f).to_string())
                 .collect::<Vec<String>>()
                 .join(", ");
-            let operation = match self.operation {
-                Operator::Plus(n) => format!("new = old + {}", n),
-                Operator::Multiply(n) => format!("new = old * {}", n),
-            };
-
             // Indent each line of the string.
             write!(
                 f,
-                "Monkey {}:\n  Starting items: {}\n  Operation: {}\n{}",
+                "Monkey {}:\n  Starting items: {}\n  Operation: new = {}\n{}",
                 self.number.0,
                 starting_items,
-                operation,
+                self.operation,
                 indent_string(&self.test.to_string(), 2)
             )
         }
@@ -125,11 +164,59 @@ mod day11 {
         }
     }
 
-    impl fmt::Display for Operator {
+    impl fmt::Display for Operand {
         fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
             match self {
-                Operator::Plus(n) => write!(f, "+ {}", n),
-                Operator::Multiply(n) => write!(f, "* {}", n),
+                Operand::Old => write!(f, "old"),
+                Operand::Literal(n) => write!(f, "{}", n),
+            }
+        }
+    }
+
+    impl fmt::Display for BinOp {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let symbol = match self {
+                BinOp::Add => '+',
+                BinOp::Sub => '-',
+                BinOp::Mul => '*',
+                BinOp::Div => '/',
+            };
+            write!(f, "{}", symbol)
+        }
+    }
+
+    impl fmt::Display for Expression {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Expression::Operand(operand) => write!(f, "{}", operand),
+                Expression::Binary(lhs, op, rhs) => {
+                    // a left child needs parentheses when it binds looser, a right child
+                    // also when it binds the same as evaluation is left to right
+                    let tighter = |child: &Expression, or_equal: bool| match child {
+                        Expression::Operand(_) => true,
+                        Expression::Binary(_, child_op, _) => {
+                            child_op.precedence() > op.precedence()
+                                || (or_equal && child_op.precedence() == op.precedence())
+                        }
+                    };
+                    let side = |child: &Expression, or_equal: bool| {
+                        if tighter(child, or_equal) {
+                            child.to_string()
+                        } else {
+                            format!("({})", child)
+                        }
+                    };
+                    write!(f, "{} {} {}", side(lhs, true), op, side(rhs, false))
+                }
+            }
+        }
+    }
+
+    impl fmt::Display for EvalError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                EvalError::Overflow => write!(f, "overflow"),
+                EvalError::DivideByZero => write!(f, "division by zero"),
             }
         }
     }
@@ -167,18 +254,42 @@ mod day11 {
         }
     }
 
-    impl InputParsable for Operator {
+    impl InputParsable for Operand {
         fn parse(input: &str) -> IResult<&str, Self> {
-            let parse_plus = map(
-                preceded(char('+'), preceded(space1, parse_number)),
-                Operator::Plus,
-            );
-            let parse_multiply = map(
-                preceded(char('*'), preceded(space1, parse_number)),
-                Operator::Multiply,
-            );
+            alt((
+                value(Operand::Old, tag("old")),
+                map(parse_number, Operand::Literal),
+            ))(input)
+        }
+    }
 
-            alt((parse_plus, parse_multiply))(input)
+    impl InputParsable for BinOp {
+        fn parse(input: &str) -> IResult<&str, Self> {
+            alt((
+                value(BinOp::Add, char('+')),
+                value(BinOp::Sub, char('-')),
+                value(BinOp::Mul, char('*')),
+                value(BinOp::Div, char('/')),
+            ))(input)
+        }
+    }
+
+    /// An operand or a parenthesised expression.
+    fn parse_term(input: &str) -> IResult<&str, Expression> {
+        alt((
+            map(Operand::parse, Expression::from),
+            delimited(char('('), Expression::parse, char(')')),
+        ))(input)
+    }
+
+    impl InputParsable for Expression {
+        fn parse(input: &str) -> IResult<&str, Self> {
+            let (input, first) = parse_term(input)?;
+            let (input, rest) = many0(tuple((
+                preceded(space1, BinOp::parse),
+                preceded(space1, parse_term),
+            )))(input)?;
+            Ok((input, Expression::from_terms(first, rest)))
         }
     }
 
@@ -253,6 +364,113 @@ mod day11 {
         }
     }
 
+    impl From<Operand> for Expression {
+        fn from(operand: Operand) -> Self {
+            Expression::Operand(operand)
+        }
+    }
+
+    impl BinOp {
+        fn binds_tighter(self) -> bool {
+            matches!(self, BinOp::Mul | BinOp::Div)
+        }
+
+        fn precedence(self) -> u8 {
+            u8::from(self.binds_tighter())
+        }
+
+        pub fn apply(self, lhs: u64, rhs: u64) -> Result<u64, EvalError> {
+            match self {
+                BinOp::Add => lhs.checked_add(rhs).ok_or(EvalError::Overflow),
+                BinOp::Sub => lhs.checked_sub(rhs).ok_or(EvalError::Overflow),
+                BinOp::Mul => lhs.checked_mul(rhs).ok_or(EvalError::Overflow),
+                BinOp::Div => lhs.checked_div(rhs).ok_or(EvalError::DivideByZero),
+            }
+        }
+    }
+
+    impl Expression {
+        pub fn binary(lhs: impl Into<Expression>, op: BinOp, rhs: impl Into<Expression>) -> Self {
+            Expression::Binary(Box::new(lhs.into()), op, Box::new(rhs.into()))
+        }
+
+        /// Builds `first op term op term ...` with `*` and `/` taking precedence.
+        pub fn from_terms<T: Into<Expression>>(
+            first: T,
+            rest: impl IntoIterator<Item = (BinOp, T)>,
+        ) -> Self {
+            let mut head = first.into();
+            let mut sums: Vec<(BinOp, Expression)> = Vec::new();
+            for (op, term) in rest {
+                if op.binds_tighter() {
+                    let last = sums.last_mut().map_or(&mut head, |(_, term)| term);
+                    let lhs = std::mem::replace(last, Operand::Old.into());
+                    *last = Expression::binary(lhs, op, term);
+                } else {
+                    sums.push((op, term.into()));
+                }
+            }
+            sums.into_iter()
+                .fold(head, |lhs, (op, rhs)| Expression::binary(lhs, op, rhs))
+        }
+
+        /// The new worry level for an item with worry `old`.
+        pub fn eval(&self, old: u64) -> Result<u64, EvalError> {
+            match self {
+                Expression::Operand(Operand::Old) => Ok(old),
+                Expression::Operand(Operand::Literal(n)) => Ok(*n),
+                Expression::Binary(lhs, op, rhs) => op.apply(lhs.eval(old)?, rhs.eval(old)?),
+            }
+        }
+
+        /// Whether only `+` and `*` are used, so results stay right modulo any number.
+        pub fn is_modular(&self) -> bool {
+            match self {
+                Expression::Operand(_) => true,
+                Expression::Binary(lhs, op, rhs) => {
+                    matches!(op, BinOp::Add | BinOp::Mul) && lhs.is_modular() && rhs.is_modular()
+                }
+            }
+        }
+    }
+
+    /// Something about a monkey that makes the input unplayable.
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    pub struct Issue {
+        pub monkey: u64,
+        pub kind: IssueKind,
+    }
+
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    pub enum IssueKind {
+        UnknownTarget(u64),
+        ThrowsToItself,
+        /// The monkey is listed at `position` but numbered differently.
+        OutOfOrder {
+            position: usize,
+        },
+        Duplicate,
+        /// `divisible by 0` cannot be tested.
+        ZeroDivisor,
+    }
+
+    impl fmt::Display for Issue {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "monkey {}: ", self.monkey)?;
+            match self.kind {
+                IssueKind::UnknownTarget(target) => {
+                    write!(f, "throws to unknown monkey {}", target)
+                }
+                IssueKind::ThrowsToItself => write!(f, "throws to itself"),
+                IssueKind::OutOfOrder { position } => {
+                    write!(f, "listed at position {}", position)
+                }
+                IssueKind::Duplicate => write!(f, "listed more than once"),
+                IssueKind::ZeroDivisor => write!(f, "tests divisibility by 0"),
+            }
+        }
+    }
+
     impl Monkeys {
         pub fn new(monkeys: Vec<Monkey>) -> Self {
             Self(monkeys)
@@ -261,17 +479,50 @@ mod day11 {
         pub fn monkeys(&self) -> &Vec<Monkey> {
             &self.0
         }
+
+        /// Checks that monkeys are numbered 0, 1, ... in order and only throw to other
+        /// monkeys, and that no test divides by zero. Empty when the input is playable.
+        pub fn validate(&self) -> Vec<Issue> {
+            let mut issues = Vec::new();
+            let mut seen = std::collections::HashSet::new();
+            for (position, monkey) in self.0.iter().enumerate() {
+                let number = monkey.number.0;
+                let mut issue = |kind| {
+                    issues.push(Issue {
+                        monkey: number,
+                        kind,
+                    })
+                };
+                if !seen.insert(number) {
+                    issue(IssueKind::Duplicate);
+                } else if number != position as u64 {
+                    issue(IssueKind::OutOfOrder { position });
+                }
+                for target in [monkey.test.if_true, monkey.test.if_false] {
+                    if target == number {
+                        issue(IssueKind::ThrowsToItself);
+                    } else if !self.0.iter().any(|m| m.number.0 == target) {
+                        issue(IssueKind::UnknownTarget(target));
+                    }
+                }
+                if monkey.test.test == 0 {
+                    issue(IssueKind::ZeroDivisor);
+                }
+            }
+            issues.dedup();
+            issues
+        }
     }
 
     fn parse_number(input: &str) -> IResult<&str, u64> {
         map_res(digit1, str::parse::<u64>)(input)
     }
 
-    fn parse_operation<'a>() -> impl Fn(&'a str) -> IResult<&str, Operator> {
+    fn parse_operation<'a>() -> impl Fn(&'a str) -> IResult<&str, Expression> {
         move |input| {
             preceded(
-                tag("  Operation: new = old "),
-                terminated(Operator::parse, line_ending),
+                tag("  Operation: new = "),
+                terminated(Expression::parse, line_ending),
             )(input)
         }
     }
@@ -289,6 +540,366 @@ mod day11 {
         }
     }
 }
+
+#[allow(dead_code)]
+mod simulation {
+    use super::day11::{EvalError, Issue, Monkey, Monkeys};
+    use std::collections::{BTreeMap, VecDeque};
+    use std::fmt;
+    use std::io::{self, Write};
+
+    #[derive(Debug, PartialEq, Clone)]
+    pub enum SimulationError {
+        /// What [`Monkeys::validate`] found wrong.
+        Invalid(Vec<Issue>),
+        /// Monkey `monkey`'s operation on an item with worry `worry` left the `u64` range.
+        Overflow {
+            round: usize,
+            monkey: u64,
+            worry: u64,
+        },
+        DivideByZero {
+            round: usize,
+            monkey: u64,
+        },
+        /// [`Relief::Modulo`] was asked for but some operation does not keep test
+        /// outcomes modulo the divisors, or their least common multiple overflows.
+        ModuloUnavailable,
+    }
+
+    impl fmt::Display for SimulationError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                SimulationError::Invalid(issues) => {
+                    let issues: Vec<_> = issues.iter().map(|i| i.to_string()).collect();
+                    write!(f, "{}", issues.join("; "))
+                }
+                SimulationError::Overflow {
+                    round,
+                    monkey,
+                    worry,
+                } => write!(
+                    f,
+                    "worry overflowed in round {} when monkey {} inspected an item at {}",
+                    round, monkey, worry
+                ),
+                SimulationError::DivideByZero { round, monkey } => {
+                    write!(f, "monkey {} divided by zero in round {}", monkey, round)
+                }
+                SimulationError::ModuloUnavailable => {
+                    write!(f, "modulo relief unavailable for this input")
+                }
+            }
+        }
+    }
+
+    /// What keeps worry levels small after each inspection.
+    #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+    pub enum Relief {
+        /// Part 1: worry is divided by three.
+        DivideByThree,
+        /// Part 2: worry is reduced modulo the least common multiple of the test divisors,
+        /// which leaves every test's outcome unchanged.
+        Modulo,
+        /// Worry only grows, soon overflowing.
+        None,
+    }
+
+    fn gcd(a: u64, b: u64) -> u64 {
+        if b == 0 {
+            a
+        } else {
+            gcd(b, a % b)
+        }
+    }
+
+    /// The least common multiple of the test divisors, `None` when it overflows.
+    pub fn divisor_lcm(monkeys: &[Monkey]) -> Option<u64> {
+        monkeys
+            .iter()
+            .map(|m| m.test.test)
+            .try_fold(1u64, |lcm, d| (lcm / gcd(lcm, d)).checked_mul(d))
+    }
+
+    /// The keep-away game: the items each monkey holds and how many it has inspected.
+    #[derive(Debug, PartialEq, Clone)]
+    pub struct KeepAway<'a> {
+        /// Valid, so numbered by position.
+        monkeys: &'a [Monkey],
+        items: Vec<Vec<u64>>,
+        ids: Vec<Vec<usize>>,
+        inspections: Vec<u64>,
+        relief: Relief,
+        /// Worry levels are taken modulo this with [`Relief::Modulo`], `None` when that
+        /// would change the outcome because an operation subtracts or divides.
+        modulus: Option<u64>,
+        round: usize,
+    }
+
+    impl<'a> KeepAway<'a> {
+        /// Refuses monkeys that do not pass [`Monkeys::validate`].
+        pub fn new(monkeys: &'a Monkeys) -> Result<Self, SimulationError> {
+            let issues = monkeys.validate();
+            if !issues.is_empty() {
+                return Err(SimulationError::Invalid(issues));
+            }
+            let monkeys = monkeys.monkeys().as_slice();
+            Ok(Self {
+                monkeys,
+                items: monkeys
+                    .iter()
+                    .map(|m| m.starting_items.items().clone())
+                    .collect(),
+                ids: monkeys
+                    .iter()
+                    .scan(0, |next, m| {
+                        let count = m.starting_items.items().len();
+                        *next += count;
+                        Some((*next - count..*next).collect())
+                    })
+                    .collect(),
+                inspections: vec![0; monkeys.len()],
+                relief: Relief::DivideByThree,
+                modulus: monkeys
+                    .iter()
+                    .all(|m| m.operation.is_modular())
+                    .then(|| divisor_lcm(monkeys))
+                    .flatten(),
+                round: 0,
+            })
+        }
+
+        /// [`Relief::DivideByThree`] unless changed, [`Relief::Modulo`] only when these
+        /// monkeys have a modulus.
+        pub fn with_relief(self, relief: Relief) -> Result<Self, SimulationError> {
+            if relief == Relief::Modulo && self.modulus.is_none() {
+                return Err(SimulationError::ModuloUnavailable);
+            }
+            Ok(Self { relief, ..self })
+        }
+
+        fn relieve(&self, worry: u64) -> u64 {
+            match self.relief {
+                Relief::DivideByThree => worry / 3,
+                Relief::Modulo => self.modulus.map_or(worry, |modulus| worry % modulus),
+                Relief::None => worry,
+            }
+        }
+
+        /// Every monkey in turn inspects and throws all of its items.
+        pub fn round(&mut self) -> Result<(), SimulationError> {
+            self.round_with(|_| ())
+        }
+
+        /// Plays a round, passing every throw to `on_throw`.
+        pub fn round_with(
+            &mut self,
+            mut on_throw: impl FnMut(Throw),
+        ) -> Result<(), SimulationError> {
+            self.round += 1;
+            for (i, monkey) in self.monkeys.iter().enumerate() {
+                let ids = std::mem::take(&mut self.ids[i]);
+                for (old, item) in std::mem::take(&mut self.items[i]).into_iter().zip(ids) {
+                    let worry = monkey.operation.eval(old).map_err(|e| match e {
+                        EvalError::Overflow => SimulationError::Overflow {
+                            round: self.round,
+                            monkey: monkey.number.0,
+                            worry: old,
+                        },
+                        EvalError::DivideByZero => SimulationError::DivideByZero {
+                            round: self.round,
+                            monkey: monkey.number.0,
+                        },
+                    })?;
+                    let worry = self.relieve(worry);
+                    let target = if worry.is_multiple_of(monkey.test.test) {
+                        monkey.test.if_true
+                    } else {
+                        monkey.test.if_false
+                    };
+                    self.items[target as usize].push(worry);
+                    self.ids[target as usize].push(item);
+                    self.inspections[i] += 1;
+                    on_throw(Throw {
+                        round: self.round,
+                        monkey: monkey.number.0,
+                        item,
+                        worry_before: old,
+                        worry_after: worry,
+                        target,
+                    });
+                }
+            }
+            Ok(())
+        }
+
+        /// The throws of the next `rounds` rounds, played a round at a time as the
+        /// iterator is consumed. An error ends it.
+        pub fn throws(&mut self, rounds: usize) -> Throws<'_, 'a> {
+            Throws {
+                until: self.round + rounds,
+                game: self,
+                buffer: VecDeque::new(),
+                error: None,
+                failed: false,
+            }
+        }
+
+        pub fn rounds(&mut self, rounds: usize) -> Result<(), SimulationError> {
+            (0..rounds).try_for_each(|_| self.round())
+        }
+
+        /// Rounds played so far.
+        pub fn rounds_played(&self) -> usize {
+            self.round
+        }
+
+        /// Worry levels held by each monkey, in input order.
+        pub fn items(&self) -> &[Vec<u64>] {
+            &self.items
+        }
+
+        /// Ids of the items each monkey holds, matching [`KeepAway::items`]. Items are
+        /// numbered from 0 in the order they start out.
+        pub fn item_ids(&self) -> &[Vec<usize>] {
+            &self.ids
+        }
+
+        pub fn inspections(&self) -> &[u64] {
+            &self.inspections
+        }
+
+        /// The product of the two highest inspection counts, `None` with fewer than two
+        /// monkeys.
+        pub fn monkey_business(&self) -> Option<u64> {
+            let mut counts = self.inspections.clone();
+            counts.sort_unstable_by(|a, b| b.cmp(a));
+            Some(counts.first()? * counts.get(1)?)
+        }
+
+        /// Inspection counts the way the puzzle shows them, e.g.
+        /// `Monkey 0 inspected items 101 times.` under `== After round 20 ==`.
+        pub fn inspection_summary(&self) -> String {
+            let mut summary = format!("== After round {} ==\n", self.round);
+            for (monkey, count) in self.monkeys.iter().zip(&self.inspections) {
+                summary += &format!(
+                    "Monkey {} inspected items {} times.\n",
+                    monkey.number.0, count
+                );
+            }
+            summary
+        }
+    }
+
+    /// One item inspected and thrown.
+    #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+    pub struct Throw {
+        pub round: usize,
+        pub monkey: u64,
+        pub item: usize,
+        pub worry_before: u64,
+        /// After the operation and the relief.
+        pub worry_after: u64,
+        pub target: u64,
+    }
+
+    pub struct Throws<'g, 'a> {
+        game: &'g mut KeepAway<'a>,
+        until: usize,
+        buffer: VecDeque<Throw>,
+        /// Reported once the throws before it are out.
+        error: Option<SimulationError>,
+        failed: bool,
+    }
+
+    impl Iterator for Throws<'_, '_> {
+        type Item = Result<Throw, SimulationError>;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            while self.buffer.is_empty() && !self.failed && self.game.round < self.until {
+                let buffer = &mut self.buffer;
+                if let Err(e) = self.game.round_with(|throw| buffer.push_back(throw)) {
+                    self.error = Some(e);
+                    self.failed = true;
+                }
+            }
+            self.buffer
+                .pop_front()
+                .map(Ok)
+                .or_else(|| self.error.take().map(Err))
+        }
+    }
+
+    /// The throws of each item in order, by item id.
+    pub fn trajectories<'t>(
+        throws: impl IntoIterator<Item = &'t Throw>,
+    ) -> BTreeMap<usize, Vec<Throw>> {
+        let mut trajectories: BTreeMap<usize, Vec<Throw>> = BTreeMap::new();
+        for throw in throws {
+            trajectories.entry(throw.item).or_default().push(*throw);
+        }
+        trajectories
+    }
+
+    /// Writes throws as CSV with a header line.
+    pub fn write_csv<'t>(
+        out: &mut impl Write,
+        throws: impl IntoIterator<Item = &'t Throw>,
+    ) -> io::Result<()> {
+        writeln!(out, "round,monkey,item,worry_before,worry_after,target")?;
+        for t in throws {
+            writeln!(
+                out,
+                "{},{},{},{},{},{}",
+                t.round, t.monkey, t.item, t.worry_before, t.worry_after, t.target
+            )?;
+        }
+        Ok(())
+    }
+}
+
+pub struct Day11Solution;
+
+impl Solution for Day11Solution {
+    type Input = day11::Monkeys;
+
+    fn parse(&self, input: &str) -> Result<day11::Monkeys, SolutionError> {
+        use day11::InputParsable;
+        match day11::Monkeys::parse(input) {
+            Ok((rest, monkeys)) if rest.trim().is_empty() => Ok(monkeys),
+            Ok((rest, _)) => Err(SolutionError::Parse(format!(
+                "unexpected input {:?}",
+                rest.lines().next().unwrap_or_default()
+            ))),
+            Err(e) => Err(SolutionError::Parse(e.to_string())),
+        }
+    }
+
+    fn part1(&self, input: &day11::Monkeys) -> Result<Answer, SolutionError> {
+        monkey_business(input, simulation::Relief::DivideByThree, 20)
+    }
+
+    fn part2(&self, input: &day11::Monkeys) -> Result<Answer, SolutionError> {
+        monkey_business(input, simulation::Relief::Modulo, 10_000)
+    }
+}
+
+fn monkey_business(
+    monkeys: &day11::Monkeys,
+    relief: simulation::Relief,
+    rounds: usize,
+) -> Result<Answer, SolutionError> {
+    let mut game = simulation::KeepAway::new(monkeys)
+        .map_err(|e| SolutionError::Parse(e.to_string()))?
+        .with_relief(relief)
+        .map_err(|e| SolutionError::NoAnswer(e.to_string()))?;
+    game.rounds(rounds)
+        .map_err(|e| SolutionError::NoAnswer(e.to_string()))?;
+    game.monkey_business()
+        .map(Answer::from)
+        .ok_or_else(|| SolutionError::NoAnswer("fewer than two monkeys".to_string()))
+}
+
 #[cfg(test)]
 mod tests_quickcheck {
     use super::day11::*;
@@ -322,24 +933,40 @@ mod tests_quickcheck {
     }
 
     #[quickcheck]
-    fn test_operator_parse_plus(num: u64) -> bool {
-        let input = format!("+ {}\n", num);
-        let result = Operator::parse(&input);
-        match result {
-            Ok((_, Operator::Plus(n))) => n == num,
-            _ => false,
-        }
+    fn test_expression_parse_plus(num: u64) -> bool {
+        let input = format!("old + {}\n", num);
+        let result = Expression::parse(&input);
+        result
+            == Ok((
+                "\n",
+                Expression::binary(Operand::Old, BinOp::Add, Operand::Literal(num)),
+            ))
     }
 
     #[quickcheck]
-    fn test_operator_parse_multiply(num: u64) -> bool {
-        let input = format!("* {}\n", num);
-        let result = Operator::parse(&input);
+    fn test_expression_parse_multiply(num: u64) -> bool {
+        let input = format!("old * {}\n", num);
+        let result = Expression::parse(&input);
         println!("debug ->  input:{:?} parsed: {:?}", num, result);
-        match result {
-            Ok((_, Operator::Multiply(n))) => n == num,
-            _ => false,
-        }
+        result
+            == Ok((
+                "\n",
+                Expression::binary(Operand::Old, BinOp::Mul, Operand::Literal(num)),
+            ))
+    }
+
+    #[quickcheck]
+    fn test_expression_round_trip(expression: Expression) -> bool {
+        Expression::parse(&expression.to_string()) == Ok(("", expression))
+    }
+
+    #[quickcheck]
+    fn test_eval_matches_checked_arithmetic(old: u64, n: u64) -> bool {
+        let eval = |op| Expression::binary(Operand::Old, op, Operand::Literal(n)).eval(old);
+        eval(BinOp::Add).ok() == old.checked_add(n)
+            && eval(BinOp::Sub).ok() == old.checked_sub(n)
+            && eval(BinOp::Mul).ok() == old.checked_mul(n)
+            && eval(BinOp::Div).ok() == old.checked_div(n)
     }
 
     #[quickcheck]
@@ -371,12 +998,12 @@ mod tests_quickcheck {
     }
 
     #[quickcheck]
-    fn test_monkey_parse(num: u64, items: Vec<u64>, op: Operator, test: MonkeyTest) -> bool {
+    fn test_monkey_parse(num: u64, items: Vec<u64>, op: Expression, test: MonkeyTest) -> bool {
         let input = format!(
-            "Monkey {}:\n  Starting items: {}\n  Operation: new = old {}\n{}",
+            "Monkey {}:\n  Starting items: {}\n  Operation: new = {}\n{}",
             num,
-            StartingItems::new(items.clone()).to_string(),
-            op.to_string(),
+            StartingItems::new(items.clone()),
+            op,
             indent_string(&test.to_string(), 2),
         );
         println!("debug ->  input:{:?}", input);
@@ -413,8 +1040,14 @@ mod tests_quickcheck {
 #[cfg(test)]
 mod day11_tests {
     use super::day11::{
-        InputParsable, Monkey, MonkeyNumber, MonkeyTest, Monkeys, Operator, StartingItems,
+        BinOp, EvalError, Expression, InputParsable, Issue, IssueKind, Monkey, MonkeyNumber,
+        MonkeyTest, Monkeys, Operand, StartingItems,
+    };
+    use super::simulation::{
+        divisor_lcm, trajectories, write_csv, KeepAway, Relief, SimulationError, Throw,
     };
+    use crate::aoc_common::file::load_input_lines;
+    use crate::aoc_common::{AocDay, AocYear, DataFileType};
 
     const MONKEY_DATA: &str = "\
 Monkey 1:
@@ -475,7 +1108,7 @@ Monkey 2:
             Monkey {
                 number: MonkeyNumber(1),
                 starting_items: StartingItems::new(vec![54, 65, 75, 74]),
-                operation: Operator::Plus(6),
+                operation: Expression::binary(Operand::Old, BinOp::Add, Operand::Literal(6)),
                 test: MonkeyTest {
                     test: 19,
                     if_true: 2,
@@ -495,7 +1128,7 @@ Monkey 2:
             Monkey {
                 number: MonkeyNumber(1),
                 starting_items: StartingItems::new(vec![54, 65, 75, 74]),
-                operation: Operator::Plus(6),
+                operation: Expression::binary(Operand::Old, BinOp::Add, Operand::Literal(6)),
                 test: MonkeyTest {
                     test: 19,
                     if_true: 2,
@@ -516,7 +1149,7 @@ Monkey 2:
                 Monkey {
                     number: MonkeyNumber(1),
                     starting_items: StartingItems::new(vec![54, 65, 75, 74]),
-                    operation: Operator::Plus(6),
+                    operation: Expression::binary(Operand::Old, BinOp::Add, Operand::Literal(6)),
                     test: MonkeyTest {
                         test: 19,
                         if_true: 2,
@@ -526,7 +1159,7 @@ Monkey 2:
                 Monkey {
                     number: MonkeyNumber(2),
                     starting_items: StartingItems::new(vec![54, 65, 75, 74]),
-                    operation: Operator::Plus(7),
+                    operation: Expression::binary(Operand::Old, BinOp::Add, Operand::Literal(7)),
                     test: MonkeyTest {
                         test: 19,
                         if_true: 2,
@@ -552,4 +1185,338 @@ Monkey 2:
             }
         );
     }
+
+    #[test]
+    fn test_solution_parse_rejects_trailing_input() {
+        use crate::aoc2022::day11_problems::Day11Solution;
+        use crate::solution::Solution;
+
+        let monkeys = Day11Solution.parse(MONKEY_DATA).unwrap();
+        assert_eq!(monkeys.monkeys().len(), 1);
+        assert!(Day11Solution
+            .parse("Monkey 1:\n  Starting items: x\n")
+            .is_err());
+    }
+
+    const SMALL: &str = "\
+Monkey 0:
+  Starting items: 10, 5
+  Operation: new = old + 2
+  Test: divisible by 4
+    If true: throw to monkey 1
+    If false: throw to monkey 2
+
+Monkey 1:
+  Starting items: 1
+  Operation: new = old * 5
+  Test: divisible by 3
+    If true: throw to monkey 2
+    If false: throw to monkey 0
+
+Monkey 2:
+  Starting items: 3
+  Operation: new = old + 1
+  Test: divisible by 7
+    If true: throw to monkey 0
+    If false: throw to monkey 1
+";
+
+    fn parsed(input: &str) -> Monkeys {
+        use crate::aoc2022::day11_problems::Day11Solution;
+        use crate::solution::Solution;
+        Day11Solution.parse(input).unwrap()
+    }
+
+    fn load(datafile_type: DataFileType) -> String {
+        load_input_lines(AocYear::Aoc2022, AocDay::Day11, datafile_type)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    #[test]
+    fn test_expression_precedence() {
+        use BinOp::*;
+        use Operand::*;
+        let (_, expression) = Expression::parse("old + 2 * old - 8 / 4").unwrap();
+        assert_eq!(
+            expression,
+            Expression::binary(
+                Expression::binary(Old, Add, Expression::binary(Literal(2), Mul, Old)),
+                Sub,
+                Expression::binary(Literal(8), Div, Literal(4))
+            )
+        );
+        assert_eq!(expression.to_string(), "old + 2 * old - 8 / 4");
+        assert_eq!(expression.eval(5), Ok(13));
+        assert_eq!(expression.eval(0), Err(EvalError::Overflow));
+        assert!(!expression.is_modular());
+        let (_, square) = Expression::parse("old * old").unwrap();
+        assert_eq!(square.eval(1 << 32), Err(EvalError::Overflow));
+        assert!(square.is_modular());
+        let (_, divide) = Expression::parse("old / 0").unwrap();
+        assert_eq!(divide.eval(1), Err(EvalError::DivideByZero));
+    }
+
+    #[test]
+    fn test_expression_parentheses() {
+        use BinOp::*;
+        use Operand::*;
+        let nested = Expression::binary(Old, Sub, Expression::binary(Old, Sub, Literal(1)));
+        assert_eq!(nested.to_string(), "old - (old - 1)");
+        let scaled = Expression::binary(Literal(2), Mul, Expression::binary(Old, Add, Literal(1)));
+        assert_eq!(scaled.to_string(), "2 * (old + 1)");
+        let (_, expression) = Expression::parse("(old + 1) * (2 - old) / 3").unwrap();
+        assert_eq!(
+            expression,
+            Expression::binary(
+                Expression::binary(
+                    Expression::binary(Old, Add, Literal(1)),
+                    Mul,
+                    Expression::binary(Literal(2), Sub, Old)
+                ),
+                Div,
+                Literal(3)
+            )
+        );
+        assert_eq!(expression.to_string(), "(old + 1) * (2 - old) / 3");
+        assert_eq!(expression.eval(1), Ok(0));
+    }
+
+    #[test]
+    fn test_modulo_needs_modular_operations() {
+        // 600 - 1 is not 600 % 84 - 1, so reducing would change the outcome
+        let monkeys = parsed(&SMALL.replace("old + 1", "old - 1"));
+        let error = KeepAway::new(&monkeys)
+            .unwrap()
+            .with_relief(Relief::Modulo)
+            .unwrap_err();
+        assert_eq!(error, SimulationError::ModuloUnavailable);
+        assert_eq!(
+            error.to_string(),
+            "modulo relief unavailable for this input"
+        );
+        assert!(KeepAway::new(&monkeys)
+            .unwrap()
+            .with_relief(Relief::None)
+            .is_ok());
+        let monkeys = parsed(&SMALL.replace("old + 1", "old / 0"));
+        let mut game = KeepAway::new(&monkeys).unwrap();
+        assert_eq!(
+            game.round(),
+            Err(SimulationError::DivideByZero {
+                round: 1,
+                monkey: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_round_throws_items() {
+        let monkeys = parsed(SMALL);
+        let mut game = KeepAway::new(&monkeys).unwrap();
+        game.round().unwrap();
+        assert_eq!(game.items(), [vec![1], vec![1, 1, 2], vec![]]);
+        assert_eq!(game.inspections(), [2, 2, 3]);
+        assert_eq!(game.monkey_business(), Some(6));
+    }
+
+    #[test]
+    fn test_invalid_monkeys_are_refused() {
+        let monkeys = parsed(&SMALL.replace("throw to monkey 2", "throw to monkey 9"));
+        let error = KeepAway::new(&monkeys).unwrap_err();
+        assert_eq!(
+            error,
+            SimulationError::Invalid(vec![
+                Issue {
+                    monkey: 0,
+                    kind: IssueKind::UnknownTarget(9)
+                },
+                Issue {
+                    monkey: 1,
+                    kind: IssueKind::UnknownTarget(9)
+                },
+            ])
+        );
+        assert_eq!(
+            error.to_string(),
+            "monkey 0: throws to unknown monkey 9; monkey 1: throws to unknown monkey 9"
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(parsed(SMALL).validate(), Vec::new());
+        let issues = |input: &str| {
+            parsed(input)
+                .validate()
+                .into_iter()
+                .map(|i| (i.monkey, i.kind))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            issues(&SMALL.replace("If false: throw to monkey 1", "If false: throw to monkey 2")),
+            [(2, IssueKind::ThrowsToItself)]
+        );
+        assert_eq!(
+            issues(&SMALL.replace("Monkey 1:", "Monkey 0:")),
+            [
+                (0, IssueKind::UnknownTarget(1)),
+                (0, IssueKind::Duplicate),
+                (0, IssueKind::ThrowsToItself),
+                (2, IssueKind::UnknownTarget(1)),
+            ]
+        );
+        assert_eq!(
+            issues(&SMALL.replace("Monkey 2:", "Monkey 3:")),
+            [
+                (0, IssueKind::UnknownTarget(2)),
+                (1, IssueKind::UnknownTarget(2)),
+                (3, IssueKind::OutOfOrder { position: 2 }),
+            ]
+        );
+        assert_eq!(
+            issues(&SMALL.replace("divisible by 7", "divisible by 0")),
+            [(2, IssueKind::ZeroDivisor)]
+        );
+    }
+
+    #[test]
+    fn test_no_monkeys_have_no_business() {
+        let monkeys = Monkeys::new(Vec::new());
+        assert_eq!(KeepAway::new(&monkeys).unwrap().monkey_business(), None);
+    }
+
+    #[test]
+    fn test_throws() {
+        let monkeys = parsed(SMALL);
+        let mut game = KeepAway::new(&monkeys).unwrap();
+        let throws: Vec<Throw> = game.throws(2).map(Result::unwrap).collect();
+        assert_eq!(game.rounds_played(), 2);
+        assert_eq!(throws.len(), 7 + 6);
+        assert_eq!(
+            throws[0],
+            Throw {
+                round: 1,
+                monkey: 0,
+                item: 0,
+                worry_before: 10,
+                worry_after: 4,
+                target: 1
+            }
+        );
+        assert_eq!(game.item_ids(), [vec![3, 1, 2], vec![0], vec![]]);
+
+        let paths = trajectories(&throws);
+        let path: Vec<_> = paths[&0].iter().map(|t| (t.round, t.monkey)).collect();
+        assert_eq!(path, [(1, 0), (1, 1), (1, 2), (2, 1), (2, 2)]);
+        assert_eq!(paths.values().map(Vec::len).sum::<usize>(), throws.len());
+
+        let mut csv = Vec::new();
+        write_csv(&mut csv, &throws[..2]).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "round,monkey,item,worry_before,worry_after,target\n1,0,0,10,4,1\n1,0,1,5,2,2\n"
+        );
+    }
+
+    #[test]
+    fn test_throws_stop_at_an_error() {
+        let monkeys = parsed(&SMALL.replace("old * 5", "old * 1000000"));
+        let mut game = KeepAway::new(&monkeys)
+            .unwrap()
+            .with_relief(Relief::None)
+            .unwrap();
+        let throws: Vec<_> = game.throws(100).collect();
+        assert!(matches!(
+            throws.last(),
+            Some(Err(SimulationError::Overflow { .. }))
+        ));
+        assert_eq!(throws.iter().filter(|t| t.is_err()).count(), 1);
+    }
+
+    #[test]
+    fn test_sample_summary() {
+        let monkeys = parsed(&load(DataFileType::SampleData));
+        let mut game = KeepAway::new(&monkeys)
+            .unwrap()
+            .with_relief(Relief::Modulo)
+            .unwrap();
+        game.round().unwrap();
+        assert_eq!(
+            game.inspection_summary(),
+            "== After round 1 ==\n\
+             Monkey 0 inspected items 2 times.\n\
+             Monkey 1 inspected items 4 times.\n\
+             Monkey 2 inspected items 3 times.\n\
+             Monkey 3 inspected items 6 times.\n"
+        );
+        game.rounds(19).unwrap();
+        assert_eq!(
+            game.inspection_summary(),
+            "== After round 20 ==\n\
+             Monkey 0 inspected items 99 times.\n\
+             Monkey 1 inspected items 97 times.\n\
+             Monkey 2 inspected items 8 times.\n\
+             Monkey 3 inspected items 103 times.\n"
+        );
+    }
+
+    #[test]
+    fn test_sample_part1() {
+        let monkeys = parsed(&load(DataFileType::SampleData));
+        let mut game = KeepAway::new(&monkeys).unwrap();
+        game.rounds(20).unwrap();
+        assert_eq!(game.inspections(), [101, 95, 7, 105]);
+        assert_eq!(game.monkey_business(), Some(10605));
+    }
+
+    #[test]
+    fn test_modulo_relief_keeps_outcomes() {
+        let monkeys = parsed(SMALL);
+        assert_eq!(divisor_lcm(monkeys.monkeys()), Some(84));
+        let play = |relief| {
+            let mut game = KeepAway::new(&monkeys)
+                .unwrap()
+                .with_relief(relief)
+                .unwrap();
+            game.rounds(8).unwrap();
+            game.inspections().to_vec()
+        };
+        assert_eq!(play(Relief::Modulo), play(Relief::None));
+        let mut game = KeepAway::new(&monkeys)
+            .unwrap()
+            .with_relief(Relief::Modulo)
+            .unwrap();
+        game.rounds(10_000).unwrap();
+        assert!(game.items().iter().flatten().all(|&worry| worry < 84));
+    }
+
+    #[test]
+    fn test_overflow_is_reported() {
+        let monkeys = parsed(&SMALL.replace("old * 5", "old * 1000000"));
+        let mut game = KeepAway::new(&monkeys)
+            .unwrap()
+            .with_relief(Relief::None)
+            .unwrap();
+        let error = game.rounds(100).unwrap_err();
+        assert!(matches!(error, SimulationError::Overflow { monkey: 1, .. }));
+        assert!(error.to_string().starts_with("worry overflowed in round"));
+        let mut game = KeepAway::new(&monkeys)
+            .unwrap()
+            .with_relief(Relief::Modulo)
+            .unwrap();
+        assert_eq!(game.rounds(100), Ok(()));
+        assert_eq!(game.rounds_played(), 100);
+    }
+
+    #[test]
+    fn test_sample_part2() {
+        let monkeys = parsed(&load(DataFileType::SampleData));
+        let mut game = KeepAway::new(&monkeys)
+            .unwrap()
+            .with_relief(Relief::Modulo)
+            .unwrap();
+        game.rounds(10_000).unwrap();
+        assert_eq!(game.inspections(), [52166, 47830, 1938, 52013]);
+        assert_eq!(game.monkey_business(), Some(2713310158));
+    }
 }
@@ -0,0 +1,471 @@
+use crate::aoc_common::{AocDay, AocInputError, AocYear, DataFileType, InputSearchPath};
+use crate::config::Config;
+use crate::solution::{Answer, Part};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Environment variable holding the adventofcode.com `session` cookie.
+pub const SESSION_ENV: &str = "AOC_SESSION";
+/// Config file key holding the `session` cookie.
+pub const SESSION_KEY: &str = "session";
+/// Environment variable overriding the website address, e.g. for a local test server.
+pub const BASE_URL_ENV: &str = "AOC_BASE_URL";
+/// Config file key overriding the website address.
+pub const BASE_URL_KEY: &str = "base_url";
+pub const DEFAULT_BASE_URL: &str = "https://adventofcode.com";
+
+const USER_AGENT: &str = "github.com/xepaul/RustAdventOfCode";
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ClientError {
+    MissingSession,
+    BadSession,
+    NotUnlocked {
+        year: AocYear,
+        day: AocDay,
+        wait: Duration,
+    },
+    Http {
+        url: String,
+        status: u16,
+        message: String,
+    },
+    Transport {
+        url: String,
+        reason: String,
+    },
+    EmptyResponse {
+        url: String,
+    },
+    Io(PathBuf, String),
+    Input(AocInputError),
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::MissingSession => write!(
+                f,
+                "no session cookie, set {} or `{}` in aoc.toml",
+                SESSION_ENV, SESSION_KEY
+            ),
+            ClientError::BadSession => {
+                write!(
+                    f,
+                    "the session cookie was rejected, log in again and update it"
+                )
+            }
+            ClientError::NotUnlocked { year, day, wait } => {
+                write!(f, "{} day {} unlocks in {}", year, day, format_wait(*wait))
+            }
+            ClientError::Http {
+                url,
+                status,
+                message,
+            } => write!(f, "{} returned {}: {}", url, status, message),
+            ClientError::Transport { url, reason } => {
+                write!(f, "could not reach {}: {}", url, reason)
+            }
+            ClientError::EmptyResponse { url } => write!(f, "{} returned nothing", url),
+            ClientError::Io(path, reason) => {
+                write!(f, "could not write {}: {}", path.display(), reason)
+            }
+            ClientError::Input(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<AocInputError> for ClientError {
+    fn from(e: AocInputError) -> Self {
+        ClientError::Input(e)
+    }
+}
+
+fn format_wait(wait: Duration) -> String {
+    let secs = wait.as_secs();
+    match (
+        secs / 86400,
+        secs % 86400 / 3600,
+        secs % 3600 / 60,
+        secs % 60,
+    ) {
+        (0, 0, 0, s) => format!("{}s", s),
+        (0, 0, m, s) => format!("{}m {}s", m, s),
+        (0, h, m, _) => format!("{}h {}m", h, m),
+        (d, h, _, _) => format!("{}d {}h", d, h),
+    }
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+/// Puzzles unlock at midnight US Eastern time (UTC-5) on their day in December.
+pub fn unlock_time(year: AocYear, day: AocDay) -> SystemTime {
+    let days = days_from_civil(year.number().into(), 12, day.number().into());
+    UNIX_EPOCH + Duration::from_secs(days as u64 * 86400 + 5 * 3600)
+}
+
+pub fn check_unlocked(year: AocYear, day: AocDay, now: SystemTime) -> Result<(), ClientError> {
+    match unlock_time(year, day).duration_since(now) {
+        Ok(wait) if !wait.is_zero() => Err(ClientError::NotUnlocked { year, day, wait }),
+        _ => Ok(()),
+    }
+}
+
+pub struct AocClient {
+    base_url: String,
+    session: Option<String>,
+    agent: ureq::Agent,
+}
+
+impl AocClient {
+    pub fn new(base_url: &str, session: Option<String>) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            session,
+            agent: ureq::AgentBuilder::new()
+                .user_agent(USER_AGENT)
+                .timeout(Duration::from_secs(30))
+                .build(),
+        }
+    }
+
+    /// Session and base url from the environment, then `aoc.toml`.
+    pub fn from_config(config: &Config) -> Self {
+        let setting = |env: &str, key: &str| {
+            std::env::var(env)
+                .ok()
+                .filter(|v| !v.is_empty())
+                .or_else(|| config.get(key).map(String::from))
+        };
+        let base_url = setting(BASE_URL_ENV, BASE_URL_KEY);
+        Self::new(
+            base_url.as_deref().unwrap_or(DEFAULT_BASE_URL),
+            setting(SESSION_ENV, SESSION_KEY),
+        )
+    }
+
+    pub fn day_url(&self, year: AocYear, day: AocDay) -> String {
+        format!("{}/{}/day/{}", self.base_url, year, day)
+    }
+
+    fn cookie(&self) -> Result<String, ClientError> {
+        self.session
+            .as_ref()
+            .map(|session| format!("session={}", session))
+            .ok_or(ClientError::MissingSession)
+    }
+
+    fn read_response(
+        url: &str,
+        response: Result<ureq::Response, ureq::Error>,
+    ) -> Result<String, ClientError> {
+        match response {
+            Ok(response) => response.into_string().map_err(|e| ClientError::Transport {
+                url: url.to_string(),
+                reason: e.to_string(),
+            }),
+            Err(ureq::Error::Status(400 | 401, _)) => Err(ClientError::BadSession),
+            Err(ureq::Error::Status(status, response)) => Err(ClientError::Http {
+                url: url.to_string(),
+                status,
+                message: response
+                    .into_string()
+                    .unwrap_or_default()
+                    .lines()
+                    .next()
+                    .unwrap_or_default()
+                    .to_string(),
+            }),
+            Err(e) => Err(ClientError::Transport {
+                url: url.to_string(),
+                reason: e.to_string(),
+            }),
+        }
+    }
+
+    pub fn download_input(&self, year: AocYear, day: AocDay) -> Result<String, ClientError> {
+        let url = format!("{}/input", self.day_url(year, day));
+        let response = self.agent.get(&url).set("Cookie", &self.cookie()?).call();
+        let body = Self::read_response(&url, response)?;
+        if body.trim().is_empty() {
+            return Err(ClientError::EmptyResponse { url });
+        }
+        Ok(body)
+    }
+
+    /// Posts an answer, returns the page the website answered with.
+    pub fn post_answer(
+        &self,
+        year: AocYear,
+        day: AocDay,
+        part: Part,
+        answer: &Answer,
+    ) -> Result<String, ClientError> {
+        let url = format!("{}/answer", self.day_url(year, day));
+        let response = self
+            .agent
+            .post(&url)
+            .set("Cookie", &self.cookie()?)
+            .send_form(&[
+                ("level", &part.to_string()),
+                ("answer", &answer.to_string()),
+            ]);
+        Self::read_response(&url, response)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum FetchOutcome {
+    Cached(PathBuf),
+    Downloaded(PathBuf),
+}
+
+fn write_file(path: &Path, contents: &str) -> Result<(), ClientError> {
+    let io_error = |e: std::io::Error| ClientError::Io(path.to_path_buf(), e.to_string());
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(io_error)?;
+    }
+    // write next to the target and rename so an interrupted download is never cached
+    let partial = path.with_extension("part");
+    std::fs::write(&partial, contents).map_err(io_error)?;
+    std::fs::rename(&partial, path).map_err(io_error)
+}
+
+/// An empty file, as left by `new`.
+fn is_placeholder(path: &Path) -> bool {
+    std::fs::metadata(path).is_ok_and(|m| m.len() == 0)
+}
+
+/// Downloads the puzzle input into the first input directory unless some input
+/// directory already has it.
+pub fn fetch_input(
+    client: &AocClient,
+    search_path: &InputSearchPath,
+    year: AocYear,
+    day: AocDay,
+    now: SystemTime,
+) -> Result<FetchOutcome, ClientError> {
+    let path = match search_path.locate(year, day, DataFileType::Data) {
+        // placeholders may sit in the repository, the input goes where a missing one would
+        Ok(path) if is_placeholder(&path) => search_path.first_path(year, day, DataFileType::Data),
+        Ok(path) => return Ok(FetchOutcome::Cached(path)),
+        Err(AocInputError::NotFound { path, .. }) => path,
+        Err(e) => return Err(e.into()),
+    };
+    check_unlocked(year, day, now)?;
+    let input = client.download_input(year, day)?;
+    write_file(&path, &input)?;
+    Ok(FetchOutcome::Downloaded(path))
+}
+
+#[cfg(test)]
+pub(crate) mod aoc_client_tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+
+    /// A stand-in for the website answering each request with the next canned
+    /// `(status, body)`, returns its base url and the raw requests it received.
+    pub(crate) fn serve(responses: Vec<(u16, &'static str)>) -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let seen = requests.clone();
+        std::thread::spawn(move || {
+            for (status, body) in responses {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut request = String::new();
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if let Some(v) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                        content_length = v.trim().parse().unwrap();
+                    }
+                    request.push_str(&line);
+                    if line == "\r\n" || line.is_empty() {
+                        break;
+                    }
+                }
+                let mut content = vec![0; content_length];
+                reader.read_exact(&mut content).unwrap();
+                request.push_str(&String::from_utf8_lossy(&content));
+                seen.lock().unwrap().push(request);
+                let response = format!(
+                    "HTTP/1.1 {} X\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                reader.get_mut().write_all(response.as_bytes()).unwrap();
+            }
+        });
+        (base_url, requests)
+    }
+
+    pub(crate) fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("aoc-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn search_path(dir: &Path) -> InputSearchPath {
+        InputSearchPath::new(Some(dir.to_path_buf()), None, &Config::default())
+    }
+
+    /// An hour after 2025 day 25 unlocks, 2025-12-25T06:00:00Z.
+    fn after_unlock() -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(1_766_638_800 + 3600)
+    }
+
+    #[test]
+    fn test_unlock_time() {
+        // 2022-12-11T05:00:00Z
+        let expected = UNIX_EPOCH + Duration::from_secs(1_670_734_800);
+        assert_eq!(unlock_time(AocYear::Aoc2022, AocDay::Day11), expected);
+        let before = expected - Duration::from_secs(90);
+        assert_eq!(
+            check_unlocked(AocYear::Aoc2022, AocDay::Day11, before),
+            Err(ClientError::NotUnlocked {
+                year: AocYear::Aoc2022,
+                day: AocDay::Day11,
+                wait: Duration::from_secs(90)
+            })
+        );
+        assert_eq!(
+            check_unlocked(AocYear::Aoc2022, AocDay::Day11, expected),
+            Ok(())
+        );
+        assert_eq!(
+            check_unlocked(AocYear::Aoc2025, AocDay::Day25, after_unlock()),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_fetch_downloads_once_then_uses_cache() {
+        let dir = scratch_dir("fetch");
+        let (base_url, requests) = serve(vec![(200, "1\n2\n3\n")]);
+        let client = AocClient::new(&base_url, Some("cookie".to_string()));
+        let path = dir.join("aoc2025/data/Day25_Data.txt");
+
+        let first = fetch_input(
+            &client,
+            &search_path(&dir),
+            AocYear::Aoc2025,
+            AocDay::Day25,
+            after_unlock(),
+        );
+        assert_eq!(first, Ok(FetchOutcome::Downloaded(path.clone())));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "1\n2\n3\n");
+
+        let second = fetch_input(
+            &client,
+            &search_path(&dir),
+            AocYear::Aoc2025,
+            AocDay::Day25,
+            after_unlock(),
+        );
+        assert_eq!(second, Ok(FetchOutcome::Cached(path)));
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].starts_with("GET /2025/day/25/input HTTP/1.1"));
+        assert!(requests[0].contains("Cookie: session=cookie"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_fetch_replaces_empty_placeholder() {
+        let dir = scratch_dir("fetch-placeholder");
+        let path = dir.join("aoc2025/data/Day25_Data.txt");
+        write_file(&path, "").unwrap();
+        let (base_url, _) = serve(vec![(200, "42\n")]);
+        let client = AocClient::new(&base_url, Some("cookie".to_string()));
+        let result = fetch_input(
+            &client,
+            &search_path(&dir),
+            AocYear::Aoc2025,
+            AocDay::Day25,
+            after_unlock(),
+        );
+        assert_eq!(result, Ok(FetchOutcome::Downloaded(path.clone())));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "42\n");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_fetch_keeps_input_out_of_placeholder_dir() {
+        let dir = scratch_dir("fetch-placeholder-elsewhere");
+        let (first, repo) = (dir.join("inputs"), dir.join("repo"));
+        let placeholder = repo.join("aoc2025/data/Day25_Data.txt");
+        write_file(&placeholder, "").unwrap();
+        let search_path = InputSearchPath::new(Some(first.clone()), Some(repo), &Config::default());
+        let (base_url, _) = serve(vec![(200, "42\n")]);
+        let client = AocClient::new(&base_url, Some("cookie".to_string()));
+        let result = fetch_input(
+            &client,
+            &search_path,
+            AocYear::Aoc2025,
+            AocDay::Day25,
+            after_unlock(),
+        );
+        let path = first.join("aoc2025/data/Day25_Data.txt");
+        assert_eq!(result, Ok(FetchOutcome::Downloaded(path.clone())));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "42\n");
+        assert_eq!(std::fs::read_to_string(&placeholder).unwrap(), "");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_fetch_refuses_locked_day_without_request() {
+        let dir = scratch_dir("fetch-locked");
+        let client = AocClient::new("http://127.0.0.1:9", Some("cookie".to_string()));
+        let now = unlock_time(AocYear::Aoc2024, AocDay::Day3) - Duration::from_secs(3600);
+        let result = fetch_input(
+            &client,
+            &search_path(&dir),
+            AocYear::Aoc2024,
+            AocDay::Day3,
+            now,
+        );
+        assert!(matches!(result, Err(ClientError::NotUnlocked { .. })));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_fetch_reports_rejected_session() {
+        let dir = scratch_dir("fetch-session");
+        let (base_url, _) = serve(vec![(400, "Puzzle inputs differ by user.  Please log in.")]);
+        let client = AocClient::new(&base_url, Some("stale".to_string()));
+        let result = fetch_input(
+            &client,
+            &search_path(&dir),
+            AocYear::Aoc2020,
+            AocDay::Day2,
+            after_unlock(),
+        );
+        assert_eq!(result, Err(ClientError::BadSession));
+        assert!(!dir.join("aoc2020/data/Day2_Data.txt").exists());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_download_needs_session() {
+        let client = AocClient::new("http://127.0.0.1:9", None);
+        let result = client.download_input(AocYear::Aoc2020, AocDay::Day2);
+        assert_eq!(result, Err(ClientError::MissingSession));
+    }
+}
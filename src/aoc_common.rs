@@ -0,0 +1,634 @@
+use crate::config::{Config, ConfigError};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Environment variable naming a directory of puzzle inputs.
+pub const INPUT_DIR_ENV: &str = "AOC_INPUT_DIR";
+/// Config file key naming a directory of puzzle inputs.
+pub const INPUT_DIR_KEY: &str = "input_dir";
+
+/// An Advent of Code event year.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone)]
+#[repr(u16)]
+pub enum AocYear {
+    Aoc2015 = 2015,
+    Aoc2016 = 2016,
+    Aoc2017 = 2017,
+    Aoc2018 = 2018,
+    Aoc2019 = 2019,
+    Aoc2020 = 2020,
+    Aoc2021 = 2021,
+    Aoc2022 = 2022,
+    Aoc2023 = 2023,
+    Aoc2024 = 2024,
+    Aoc2025 = 2025,
+}
+
+/// A puzzle day within an event.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone)]
+#[repr(u8)]
+pub enum AocDay {
+    Day1 = 1,
+    Day2 = 2,
+    Day3 = 3,
+    Day4 = 4,
+    Day5 = 5,
+    Day6 = 6,
+    Day7 = 7,
+    Day8 = 8,
+    Day9 = 9,
+    Day10 = 10,
+    Day11 = 11,
+    Day12 = 12,
+    Day13 = 13,
+    Day14 = 14,
+    Day15 = 15,
+    Day16 = 16,
+    Day17 = 17,
+    Day18 = 18,
+    Day19 = 19,
+    Day20 = 20,
+    Day21 = 21,
+    Day22 = 22,
+    Day23 = 23,
+    Day24 = 24,
+    Day25 = 25,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone)]
+pub enum DataFileType {
+    Data,
+    SampleData,
+    Debug,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum AocDateError {
+    Year(String),
+    Day(String),
+}
+
+impl fmt::Display for AocDateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AocDateError::Year(s) => write!(
+                f,
+                "{:?} is not an event year ({} to {})",
+                s,
+                AocYear::FIRST,
+                AocYear::LAST
+            ),
+            AocDateError::Day(s) => write!(f, "{:?} is not a puzzle day (1 to 25)", s),
+        }
+    }
+}
+
+impl AocYear {
+    pub const ALL: [AocYear; 11] = [
+        AocYear::Aoc2015,
+        AocYear::Aoc2016,
+        AocYear::Aoc2017,
+        AocYear::Aoc2018,
+        AocYear::Aoc2019,
+        AocYear::Aoc2020,
+        AocYear::Aoc2021,
+        AocYear::Aoc2022,
+        AocYear::Aoc2023,
+        AocYear::Aoc2024,
+        AocYear::Aoc2025,
+    ];
+    pub const FIRST: AocYear = AocYear::Aoc2015;
+    pub const LAST: AocYear = AocYear::Aoc2025;
+
+    pub fn number(self) -> u16 {
+        self as u16
+    }
+
+    pub fn iter() -> impl Iterator<Item = AocYear> {
+        Self::ALL.into_iter()
+    }
+}
+
+impl AocDay {
+    pub const ALL: [AocDay; 25] = [
+        AocDay::Day1,
+        AocDay::Day2,
+        AocDay::Day3,
+        AocDay::Day4,
+        AocDay::Day5,
+        AocDay::Day6,
+        AocDay::Day7,
+        AocDay::Day8,
+        AocDay::Day9,
+        AocDay::Day10,
+        AocDay::Day11,
+        AocDay::Day12,
+        AocDay::Day13,
+        AocDay::Day14,
+        AocDay::Day15,
+        AocDay::Day16,
+        AocDay::Day17,
+        AocDay::Day18,
+        AocDay::Day19,
+        AocDay::Day20,
+        AocDay::Day21,
+        AocDay::Day22,
+        AocDay::Day23,
+        AocDay::Day24,
+        AocDay::Day25,
+    ];
+
+    pub fn number(self) -> u8 {
+        self as u8
+    }
+
+    pub fn iter() -> impl Iterator<Item = AocDay> {
+        Self::ALL.into_iter()
+    }
+}
+
+impl TryFrom<u16> for AocYear {
+    type Error = AocDateError;
+    fn try_from(v: u16) -> Result<Self, AocDateError> {
+        Self::iter()
+            .find(|y| y.number() == v)
+            .ok_or_else(|| AocDateError::Year(v.to_string()))
+    }
+}
+
+impl TryFrom<u8> for AocDay {
+    type Error = AocDateError;
+    fn try_from(v: u8) -> Result<Self, AocDateError> {
+        Self::iter()
+            .find(|d| d.number() == v)
+            .ok_or_else(|| AocDateError::Day(v.to_string()))
+    }
+}
+
+impl FromStr for AocYear {
+    type Err = AocDateError;
+    fn from_str(s: &str) -> Result<Self, AocDateError> {
+        let digits = s.trim().trim_start_matches("aoc");
+        digits
+            .parse::<u16>()
+            .map_err(|_| AocDateError::Year(s.to_string()))
+            .and_then(|n| AocYear::try_from(n).map_err(|_| AocDateError::Year(s.to_string())))
+    }
+}
+
+impl FromStr for AocDay {
+    type Err = AocDateError;
+    fn from_str(s: &str) -> Result<Self, AocDateError> {
+        let digits = s.trim().trim_start_matches("day");
+        digits
+            .parse::<u8>()
+            .map_err(|_| AocDateError::Day(s.to_string()))
+            .and_then(|n| AocDay::try_from(n).map_err(|_| AocDateError::Day(s.to_string())))
+    }
+}
+
+impl fmt::Display for AocYear {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.number())
+    }
+}
+
+impl fmt::Display for AocDay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.number())
+    }
+}
+
+impl fmt::Display for DataFileType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DataFileType::Data => write!(f, "data"),
+            DataFileType::SampleData => write!(f, "sample"),
+            DataFileType::Debug => write!(f, "debug"),
+        }
+    }
+}
+
+/// Location of a data file below an input directory, e.g. `aoc2020/data/Day8_Data.txt`.
+pub fn data_file_relative_path(year: AocYear, day: AocDay, datafile_type: DataFileType) -> PathBuf {
+    let filename_suffix = match datafile_type {
+        DataFileType::Data => "",
+        DataFileType::SampleData => "Example",
+        DataFileType::Debug => "Debug",
+    };
+    PathBuf::from(format!("aoc{}", year))
+        .join("data")
+        .join(format!("Day{}_Data{}.txt", day, filename_suffix))
+}
+
+/// The input directory inside this repository's source tree.
+pub fn builtin_input_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src")
+}
+
+#[allow(dead_code)]
+pub fn get_data_file_path(year: AocYear, day: AocDay, datafile_type: DataFileType) -> String {
+    builtin_input_dir()
+        .join(data_file_relative_path(year, day, datafile_type))
+        .display()
+        .to_string()
+}
+
+/// Where an input directory in the search path came from.
+#[derive(Debug, PartialEq, Clone)]
+pub enum InputDirOrigin {
+    Flag,
+    Env,
+    Config(PathBuf),
+    BuiltIn,
+}
+
+impl fmt::Display for InputDirOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputDirOrigin::Flag => write!(f, "--input-dir"),
+            InputDirOrigin::Env => write!(f, "{}", INPUT_DIR_ENV),
+            InputDirOrigin::Config(path) => write!(f, "{} in {}", INPUT_DIR_KEY, path.display()),
+            InputDirOrigin::BuiltIn => write!(f, "built-in"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct InputDir {
+    pub origin: InputDirOrigin,
+    pub dir: PathBuf,
+}
+
+/// Why an input file could not be loaded.
+#[derive(Debug, PartialEq, Clone)]
+pub enum AocInputError {
+    /// No search directory has the file, `path` is where it should be created.
+    NotFound {
+        path: PathBuf,
+        year: AocYear,
+        day: AocDay,
+        datafile_type: DataFileType,
+        tried: Vec<(InputDirOrigin, PathBuf)>,
+    },
+    Config(ConfigError),
+    Io {
+        path: PathBuf,
+        reason: String,
+    },
+    InvalidUtf8 {
+        path: PathBuf,
+        line: usize,
+    },
+    Empty {
+        path: PathBuf,
+    },
+}
+
+impl fmt::Display for AocInputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AocInputError::NotFound {
+                path,
+                year,
+                day,
+                datafile_type,
+                tried,
+            } => {
+                write!(
+                    f,
+                    "no {} input for {} day {}, create {}",
+                    datafile_type,
+                    year,
+                    day,
+                    path.display()
+                )?;
+                write!(f, "\nlocations tried:")?;
+                for (origin, path) in tried {
+                    write!(f, "\n  {} ({})", path.display(), origin)?;
+                }
+                Ok(())
+            }
+            AocInputError::Config(e) => write!(f, "{}", e),
+            AocInputError::Io { path, reason } => {
+                write!(f, "could not read {}: {}", path.display(), reason)
+            }
+            AocInputError::InvalidUtf8 { path, line } => {
+                write!(f, "{} is not valid UTF-8 (line {})", path.display(), line)
+            }
+            AocInputError::Empty { path } => {
+                write!(
+                    f,
+                    "{} is empty, paste the puzzle input into it",
+                    path.display()
+                )
+            }
+        }
+    }
+}
+
+impl From<ConfigError> for AocInputError {
+    fn from(e: ConfigError) -> Self {
+        AocInputError::Config(e)
+    }
+}
+
+/// Checks raw file contents are non-empty UTF-8.
+pub fn decode_input(path: &Path, bytes: Vec<u8>) -> Result<String, AocInputError> {
+    let text = String::from_utf8(bytes).map_err(|e| {
+        let valid = &e.as_bytes()[..e.utf8_error().valid_up_to()];
+        AocInputError::InvalidUtf8 {
+            path: path.to_path_buf(),
+            line: valid.iter().filter(|b| **b == b'\n').count() + 1,
+        }
+    })?;
+    if text.trim().is_empty() {
+        return Err(AocInputError::Empty {
+            path: path.to_path_buf(),
+        });
+    }
+    Ok(text)
+}
+
+/// Reads an input file from an explicit path.
+pub fn read_input_file(path: &Path) -> Result<String, AocInputError> {
+    let bytes = std::fs::read(path).map_err(|e| AocInputError::Io {
+        path: path.to_path_buf(),
+        reason: e.to_string(),
+    })?;
+    decode_input(path, bytes)
+}
+
+/// Ordered input directories: `--input-dir`, `$AOC_INPUT_DIR`, `input_dir` from
+/// `aoc.toml`, then the repository's own `src` directory.
+#[derive(Debug, PartialEq, Clone)]
+pub struct InputSearchPath(Vec<InputDir>);
+
+impl InputSearchPath {
+    pub fn new(flag: Option<PathBuf>, env: Option<PathBuf>, config: &Config) -> Self {
+        let config_dir = config.get_path(INPUT_DIR_KEY).map(|dir| InputDir {
+            origin: InputDirOrigin::Config(
+                config.path().map(Path::to_path_buf).unwrap_or_default(),
+            ),
+            dir,
+        });
+        let dirs = [
+            flag.map(|dir| InputDir {
+                origin: InputDirOrigin::Flag,
+                dir,
+            }),
+            env.map(|dir| InputDir {
+                origin: InputDirOrigin::Env,
+                dir,
+            }),
+            config_dir,
+            Some(InputDir {
+                origin: InputDirOrigin::BuiltIn,
+                dir: builtin_input_dir(),
+            }),
+        ];
+        Self(dirs.into_iter().flatten().collect())
+    }
+
+    /// Search path from `$AOC_INPUT_DIR` and the project config, plus an optional flag.
+    pub fn from_environment(flag: Option<PathBuf>) -> Result<Self, AocInputError> {
+        let env = std::env::var_os(INPUT_DIR_ENV)
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from);
+        Ok(Self::new(flag, env, &Config::load()?))
+    }
+
+    /// Where a new input file goes: the first input directory.
+    pub fn first_path(&self, year: AocYear, day: AocDay, datafile_type: DataFileType) -> PathBuf {
+        let relative = data_file_relative_path(year, day, datafile_type);
+        self.0
+            .first()
+            .map_or_else(builtin_input_dir, |input_dir| input_dir.dir.clone())
+            .join(relative)
+    }
+
+    pub fn locate(
+        &self,
+        year: AocYear,
+        day: AocDay,
+        datafile_type: DataFileType,
+    ) -> Result<PathBuf, AocInputError> {
+        let relative = data_file_relative_path(year, day, datafile_type);
+        let tried: Vec<_> = self
+            .0
+            .iter()
+            .map(|input_dir| (input_dir.origin.clone(), input_dir.dir.join(&relative)))
+            .collect();
+        match tried.iter().find(|(_, path)| path.is_file()) {
+            Some((_, path)) => Ok(path.clone()),
+            None => Err(AocInputError::NotFound {
+                path: self.first_path(year, day, datafile_type),
+                year,
+                day,
+                datafile_type,
+                tried,
+            }),
+        }
+    }
+}
+
+/// A nom parser for a piece of puzzle input.
+pub trait InputParsable {
+    fn parse(input: &str) -> nom::IResult<&str, Self>
+    where
+        Self: Sized;
+}
+
+pub mod file {
+    use super::*;
+
+    #[allow(dead_code)]
+    pub fn load_input_lines(
+        year: AocYear,
+        day: AocDay,
+        datafile_type: DataFileType,
+    ) -> Result<String, AocInputError> {
+        let search_path = InputSearchPath::from_environment(None)?;
+        load_input_lines_from(&search_path, year, day, datafile_type)
+    }
+
+    pub fn load_input_lines_from(
+        search_path: &InputSearchPath,
+        year: AocYear,
+        day: AocDay,
+        datafile_type: DataFileType,
+    ) -> Result<String, AocInputError> {
+        let file_path = search_path.locate(year, day, datafile_type)?;
+        read_input_file(&file_path)
+    }
+}
+
+pub mod file_async {
+    use super::*;
+
+    use async_std::fs;
+    #[allow(dead_code)]
+    pub async fn load_input_lines_async(
+        year: AocYear,
+        day: AocDay,
+        datafile_type: DataFileType,
+    ) -> Result<String, AocInputError> {
+        let search_path = InputSearchPath::from_environment(None)?;
+        let file_path = search_path.locate(year, day, datafile_type)?;
+        let bytes = fs::read(&file_path).await.map_err(|e| AocInputError::Io {
+            path: file_path.clone(),
+            reason: e.to_string(),
+        })?;
+        decode_input(&file_path, bytes)
+    }
+}
+
+#[cfg(test)]
+mod aoc_common_tests {
+    use super::*;
+
+    #[test]
+    fn test_year_parse_and_display() {
+        assert_eq!("2022".parse::<AocYear>(), Ok(AocYear::Aoc2022));
+        assert_eq!("aoc2015".parse::<AocYear>(), Ok(AocYear::Aoc2015));
+        assert_eq!(AocYear::Aoc2020.to_string(), "2020");
+        assert_eq!(
+            "2014".parse::<AocYear>(),
+            Err(AocDateError::Year("2014".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_day_parse_and_display() {
+        assert_eq!("8".parse::<AocDay>(), Ok(AocDay::Day8));
+        assert_eq!("day25".parse::<AocDay>(), Ok(AocDay::Day25));
+        assert_eq!(AocDay::Day11.to_string(), "11");
+        assert_eq!(
+            "0".parse::<AocDay>(),
+            Err(AocDateError::Day("0".to_string()))
+        );
+        assert_eq!(
+            "x".parse::<AocDay>(),
+            Err(AocDateError::Day("x".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_try_from_numbers() {
+        assert_eq!(AocDay::try_from(25), Ok(AocDay::Day25));
+        assert!(AocDay::try_from(26).is_err());
+        assert_eq!(AocYear::try_from(2021), Ok(AocYear::Aoc2021));
+        assert!(AocYear::try_from(2026).is_err());
+    }
+
+    #[test]
+    fn test_iteration_covers_every_day_in_order() {
+        let days: Vec<u8> = AocDay::iter().map(AocDay::number).collect();
+        assert_eq!(days, (1..=25).collect::<Vec<u8>>());
+        assert_eq!(AocYear::iter().next(), Some(AocYear::FIRST));
+        assert_eq!(AocYear::iter().last(), Some(AocYear::LAST));
+    }
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("aoc-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_search_path_order() {
+        let config = Config::parse(Some(PathBuf::from("/p/aoc.toml")), "input_dir = in").unwrap();
+        let search_path = InputSearchPath::new(
+            Some(PathBuf::from("/flag")),
+            Some(PathBuf::from("/env")),
+            &config,
+        );
+        let dirs: Vec<_> = search_path.0.iter().map(|d| d.dir.clone()).collect();
+        assert_eq!(
+            dirs,
+            vec![
+                PathBuf::from("/flag"),
+                PathBuf::from("/env"),
+                PathBuf::from("/p/in"),
+                builtin_input_dir()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_locate_prefers_earlier_dirs() {
+        let dir = scratch_dir("locate");
+        let relative = data_file_relative_path(AocYear::Aoc2020, AocDay::Day1, DataFileType::Data);
+        std::fs::create_dir_all(dir.join(&relative).parent().unwrap()).unwrap();
+        std::fs::write(dir.join(&relative), "1\n").unwrap();
+
+        let search_path = InputSearchPath::new(Some(dir.clone()), None, &Config::default());
+        let found = search_path.locate(AocYear::Aoc2020, AocDay::Day1, DataFileType::Data);
+        assert_eq!(found, Ok(dir.join(&relative)));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_locate_reports_every_location_tried() {
+        let search_path =
+            InputSearchPath::new(None, Some(PathBuf::from("/env")), &Config::default());
+        let err = search_path
+            .locate(AocYear::Aoc2016, AocDay::Day3, DataFileType::SampleData)
+            .unwrap_err();
+        match &err {
+            AocInputError::NotFound { path, tried, .. } => {
+                assert_eq!(
+                    path,
+                    &PathBuf::from("/env/aoc2016/data/Day3_DataExample.txt")
+                );
+                assert_eq!(tried.len(), 2);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        let message = err.to_string();
+        assert!(message.starts_with(
+            "no sample input for 2016 day 3, create /env/aoc2016/data/Day3_DataExample.txt"
+        ));
+        assert!(message.contains("/env/aoc2016/data/Day3_DataExample.txt (AOC_INPUT_DIR)"));
+        assert!(message.contains("(built-in)"));
+    }
+
+    #[test]
+    fn test_read_input_file_errors() {
+        let dir = scratch_dir("read");
+        let empty = dir.join("empty.txt");
+        std::fs::write(&empty, " \n").unwrap();
+        assert_eq!(
+            read_input_file(&empty),
+            Err(AocInputError::Empty {
+                path: empty.clone()
+            })
+        );
+
+        let binary = dir.join("binary.txt");
+        std::fs::write(&binary, b"ok\nstill ok\n\xff\n").unwrap();
+        assert_eq!(
+            read_input_file(&binary),
+            Err(AocInputError::InvalidUtf8 {
+                path: binary.clone(),
+                line: 3
+            })
+        );
+
+        let missing = dir.join("missing.txt");
+        assert!(matches!(
+            read_input_file(&missing),
+            Err(AocInputError::Io { path, .. }) if path == missing
+        ));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_data_file_path() {
+        let path = get_data_file_path(AocYear::Aoc2022, AocDay::Day11, DataFileType::SampleData);
+        assert!(path.ends_with("/src/aoc2022/data/Day11_DataExample.txt"));
+    }
+}
@@ -0,0 +1,256 @@
+use crate::aoc_common::{AocDay, AocYear};
+use crate::solution::{DynSolution, Part, SolutionError};
+use std::time::{Duration, Instant};
+
+/// Summary of repeated timings of one step.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Stats {
+    pub iterations: usize,
+    pub min: Duration,
+    pub median: Duration,
+    pub mean: Duration,
+    pub p95: Duration,
+}
+
+impl Stats {
+    /// `None` when there are no samples.
+    pub fn from_samples(mut samples: Vec<Duration>) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        samples.sort();
+        let n = samples.len();
+        let median = if n.is_multiple_of(2) {
+            (samples[n / 2 - 1] + samples[n / 2]) / 2
+        } else {
+            samples[n / 2]
+        };
+        // nearest rank
+        let p95 = samples[(n * 95).div_ceil(100) - 1];
+        Some(Self {
+            iterations: n,
+            min: samples[0],
+            median,
+            mean: samples.iter().sum::<Duration>() / n as u32,
+            p95,
+        })
+    }
+
+    fn to_json(self) -> String {
+        format!(
+            "{{\"iterations\": {}, \"min_ns\": {}, \"median_ns\": {}, \"mean_ns\": {}, \"p95_ns\": {}}}",
+            self.iterations,
+            self.min.as_nanos(),
+            self.median.as_nanos(),
+            self.mean.as_nanos(),
+            self.p95.as_nanos()
+        )
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct BenchReport {
+    pub year: AocYear,
+    pub day: AocDay,
+    pub input: String,
+    pub parse: Stats,
+    pub part1: Result<Stats, SolutionError>,
+    pub part2: Result<Stats, SolutionError>,
+}
+
+fn time<T>(f: impl FnOnce() -> T) -> Duration {
+    let start = Instant::now();
+    std::hint::black_box(f());
+    start.elapsed()
+}
+
+/// Times `f` `iterations` times, at least once.
+pub fn measure<T>(iterations: usize, mut f: impl FnMut() -> T) -> Stats {
+    let samples = (0..iterations.max(1)).map(|_| time(&mut f)).collect();
+    Stats::from_samples(samples).expect("at least one iteration")
+}
+
+fn bench_part(
+    solution: &dyn DynSolution,
+    part: Part,
+    parsed: &dyn std::any::Any,
+    iterations: usize,
+) -> Result<Stats, SolutionError> {
+    // the first run doubles as a warm up and checks the part has an answer
+    solution.solve_parsed(part, parsed)?;
+    Ok(measure(iterations, || solution.solve_parsed(part, parsed)))
+}
+
+/// Times parsing and both parts separately, each `iterations` times.
+pub fn bench(
+    year: AocYear,
+    day: AocDay,
+    solution: &dyn DynSolution,
+    input_name: &str,
+    input: &str,
+    iterations: usize,
+) -> Result<BenchReport, SolutionError> {
+    let iterations = iterations.max(1);
+    let parsed = solution.parse_any(input)?;
+    Ok(BenchReport {
+        year,
+        day,
+        input: input_name.to_string(),
+        parse: measure(iterations, || solution.parse_any(input)),
+        part1: bench_part(solution, Part::One, parsed.as_ref(), iterations),
+        part2: bench_part(solution, Part::Two, parsed.as_ref(), iterations),
+    })
+}
+
+impl BenchReport {
+    pub fn format_table(&self) -> String {
+        let mut table = format!(
+            "{} day {} ({})\n{:<6} {:>12} {:>12} {:>12} {:>12}\n",
+            self.year, self.day, self.input, "step", "min", "median", "mean", "p95"
+        );
+        let steps = [
+            ("parse", Ok(self.parse)),
+            ("part 1", self.part1.clone()),
+            ("part 2", self.part2.clone()),
+        ];
+        for (name, stats) in steps {
+            let line = match stats {
+                Ok(s) => format!(
+                    "{:<6} {:>12} {:>12} {:>12} {:>12}",
+                    name,
+                    format!("{:.2?}", s.min),
+                    format!("{:.2?}", s.median),
+                    format!("{:.2?}", s.mean),
+                    format!("{:.2?}", s.p95)
+                ),
+                Err(e) => format!("{:<6} {}", name, e),
+            };
+            table.push_str(&line);
+            table.push('\n');
+        }
+        table.push_str(&format!("{} iterations", self.parse.iterations));
+        table
+    }
+
+    /// Durations are in nanoseconds, a part without timings is `null`.
+    pub fn to_json(&self) -> String {
+        let part = |stats: &Result<Stats, SolutionError>| {
+            stats.as_ref().map_or("null".to_string(), |s| s.to_json())
+        };
+        format!(
+            "{{\"year\": {}, \"day\": {}, \"input\": {}, \"parse\": {}, \"part1\": {}, \"part2\": {}}}",
+            self.year,
+            self.day,
+            json_string(&self.input),
+            self.parse.to_json(),
+            part(&self.part1),
+            part(&self.part2)
+        )
+    }
+}
+
+/// `s` as a quoted JSON string.
+fn json_string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod bench_tests {
+    use super::*;
+    use crate::aoc2020::day1_problems::Day1Solution;
+
+    fn ms(v: u64) -> Duration {
+        Duration::from_millis(v)
+    }
+
+    #[test]
+    fn test_stats_from_samples() {
+        let samples = (1..=20).rev().map(ms).collect();
+        let stats = Stats::from_samples(samples).unwrap();
+        assert_eq!(stats.iterations, 20);
+        assert_eq!(stats.min, ms(1));
+        assert_eq!(stats.median, Duration::from_micros(10_500));
+        assert_eq!(stats.mean, Duration::from_micros(10_500));
+        assert_eq!(stats.p95, ms(19));
+    }
+
+    #[test]
+    fn test_stats_single_sample_and_empty() {
+        let stats = Stats::from_samples(vec![ms(3)]).unwrap();
+        assert_eq!((stats.min, stats.median, stats.p95), (ms(3), ms(3), ms(3)));
+        assert_eq!(Stats::from_samples(vec![]), None);
+    }
+
+    #[test]
+    fn test_bench_times_each_step() {
+        let input = "1721\n979\n366\n299\n675\n1456";
+        let report = bench(
+            AocYear::Aoc2020,
+            AocDay::Day1,
+            &Day1Solution,
+            "sample",
+            input,
+            5,
+        )
+        .unwrap();
+        assert_eq!(report.parse.iterations, 5);
+        assert_eq!(report.part1.map(|s| s.iterations), Ok(5));
+        assert_eq!(report.part2.map(|s| s.iterations), Ok(5));
+    }
+
+    #[test]
+    fn test_bench_json_marks_missing_parts_null() {
+        let stats = Stats::from_samples(vec![ms(1), ms(3)]).unwrap();
+        let report = BenchReport {
+            year: AocYear::Aoc2020,
+            day: AocDay::Day8,
+            input: "sample".to_string(),
+            parse: stats,
+            part1: Ok(stats),
+            part2: Err(SolutionError::NotImplemented),
+        };
+        let stats_json = "{\"iterations\": 2, \"min_ns\": 1000000, \"median_ns\": 2000000, \"mean_ns\": 2000000, \"p95_ns\": 3000000}";
+        assert_eq!(
+            report.to_json(),
+            format!(
+                "{{\"year\": 2020, \"day\": 8, \"input\": \"sample\", \"parse\": {}, \"part1\": {}, \"part2\": null}}",
+                stats_json, stats_json
+            )
+        );
+    }
+
+    #[test]
+    fn test_json_string_escapes() {
+        assert_eq!(json_string("sample"), "\"sample\"");
+        assert_eq!(
+            json_string("it's \"a\"\\b\n\u{1b}\u{7f}é"),
+            "\"it's \\\"a\\\"\\\\b\\n\\u001b\\u007fé\""
+        );
+        let stats = Stats::from_samples(vec![ms(1)]).unwrap();
+        let report = BenchReport {
+            year: AocYear::Aoc2020,
+            day: AocDay::Day1,
+            input: "in\u{1b}put's \"dir\"/x.txt".to_string(),
+            parse: stats,
+            part1: Ok(stats),
+            part2: Ok(stats),
+        };
+        assert!(report
+            .to_json()
+            .contains("\"input\": \"in\\u001bput's \\\"dir\\\"/x.txt\","));
+    }
+}
@@ -0,0 +1,405 @@
+use crate::answers::AnswerKey;
+use crate::answers::{answers_path, Answers, AnswersError};
+use crate::aoc_client::{fetch_input, AocClient, ClientError, FetchOutcome};
+use crate::aoc_common::DataFileType;
+use crate::aoc_common::{builtin_input_dir, AocDay, AocYear, InputSearchPath};
+use crate::bench;
+use crate::config::Config;
+use crate::runner::{self, DebugOptions, InputSource, RunError};
+use crate::scaffold::{self, ScaffoldError};
+use crate::solution::Part;
+use crate::submit::{self, SubmissionLog, SubmitError, Verdict};
+use crate::verify;
+use std::fmt;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+pub const USAGE: &str = "\
+usage: adventofcode <command> [options]
+
+commands:
+  run --year <year> --day <day> --part <part> [--input sample|data|<path>]
+  verify [--record] [--answers <file>]
+      check every solution against the known answers, --record stores
+      answers that are not known yet
+  bench --year <year> --day <day> [--input sample|data|<path>]
+        [--iterations <n>] [--json <file>|-]
+      time parsing and each part, --json writes the statistics as JSON
+  fetch --year <year> --day <day>
+      download the puzzle input into the first input directory, needs
+      $AOC_SESSION or session in aoc.toml
+  submit --year <year> --day <day> --part <part>
+      post the answer for the data input, refuses answers already
+      rejected and waits out the cooldown recorded in submissions.txt
+  debug --year <year> --day <day> [--input sample|data|<path>]
+        [--break <pc>|nop|jmp|acc,...] [--watch changed|=N|>N|<N,...]
+        [--trace <file>]
+      run a VM puzzle's program and report every breakpoint, watch and
+      the halt, --trace writes `pc instruction acc_before acc_after` lines
+  new --year <year> --day <day>
+      generate the module for a day, register it and create empty
+      data files
+
+options:
+  --input-dir <dir>   look for inputs in <dir> before $AOC_INPUT_DIR,
+                      input_dir in aoc.toml and the built-in src directory";
+
+#[derive(Debug, PartialEq)]
+pub enum CliError {
+    Usage(String),
+    Run(RunError),
+    Answers(AnswersError),
+    Client(ClientError),
+    Submit(SubmitError),
+    Scaffold(ScaffoldError),
+    Failed(String),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::Usage(msg) => write!(f, "{}\n\n{}", msg, USAGE),
+            CliError::Run(e) => write!(f, "{}", e),
+            CliError::Answers(e) => write!(f, "{}", e),
+            CliError::Client(e) => write!(f, "{}", e),
+            CliError::Submit(e) => write!(f, "{}", e),
+            CliError::Scaffold(e) => write!(f, "{}", e),
+            CliError::Failed(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl From<AnswersError> for CliError {
+    fn from(e: AnswersError) -> Self {
+        CliError::Answers(e)
+    }
+}
+
+impl From<ClientError> for CliError {
+    fn from(e: ClientError) -> Self {
+        CliError::Client(e)
+    }
+}
+
+impl From<SubmitError> for CliError {
+    fn from(e: SubmitError) -> Self {
+        CliError::Submit(e)
+    }
+}
+
+impl From<ScaffoldError> for CliError {
+    fn from(e: ScaffoldError) -> Self {
+        CliError::Scaffold(e)
+    }
+}
+
+impl From<RunError> for CliError {
+    fn from(e: RunError) -> Self {
+        CliError::Run(e)
+    }
+}
+
+/// Flags that take no value.
+const SWITCHES: &[&str] = &["record"];
+
+/// `--name value` pairs and `--switch`es following the subcommand.
+#[derive(Debug, PartialEq)]
+pub struct Flags(Vec<(String, String)>);
+
+impl Flags {
+    pub fn parse(args: &[String]) -> Result<Self, CliError> {
+        let mut flags = Vec::new();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let name = arg
+                .strip_prefix("--")
+                .ok_or_else(|| CliError::Usage(format!("unexpected argument {:?}", arg)))?;
+            if SWITCHES.contains(&name) {
+                flags.push((name.to_string(), String::new()));
+                continue;
+            }
+            let value = args
+                .next()
+                .ok_or_else(|| CliError::Usage(format!("missing value for --{}", name)))?;
+            flags.push((name.to_string(), value.clone()));
+        }
+        Ok(Self(flags))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .rev()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn switch(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    pub fn required(&self, name: &str) -> Result<&str, CliError> {
+        self.get(name)
+            .ok_or_else(|| CliError::Usage(format!("missing --{}", name)))
+    }
+
+    pub fn number<T: std::str::FromStr>(&self, name: &str) -> Result<T, CliError> {
+        let value = self.required(name)?;
+        value
+            .parse()
+            .map_err(|_| CliError::Usage(format!("--{} expects a number, got {:?}", name, value)))
+    }
+
+    pub fn year(&self) -> Result<AocYear, CliError> {
+        self.required("year")?
+            .parse()
+            .map_err(|e| CliError::Usage(format!("--year: {}", e)))
+    }
+
+    pub fn day(&self) -> Result<AocDay, CliError> {
+        self.required("day")?
+            .parse()
+            .map_err(|e| CliError::Usage(format!("--day: {}", e)))
+    }
+
+    pub fn part(&self) -> Result<Part, CliError> {
+        Part::try_from(self.number::<u8>("part")?)
+            .map_err(|p| CliError::Usage(format!("--part must be 1 or 2, got {}", p)))
+    }
+
+    pub fn search_path(&self) -> Result<InputSearchPath, CliError> {
+        let flag = self.get("input-dir").map(PathBuf::from);
+        InputSearchPath::from_environment(flag).map_err(|e| CliError::Run(RunError::Input(e)))
+    }
+}
+
+fn run_command(flags: &Flags) -> Result<(), CliError> {
+    let year = flags.year()?;
+    let day = flags.day()?;
+    let part = flags.part()?;
+    let input = InputSource::from_arg(flags.get("input").unwrap_or("data"));
+    let result = runner::run(year, day, part, &input, &flags.search_path()?)?;
+    println!(
+        "{} day {} part {}: {} ({:?})",
+        year, day, part, result.answer, result.elapsed
+    );
+    Ok(())
+}
+
+fn verify_command(flags: &Flags) -> Result<(), CliError> {
+    let search_path = flags.search_path()?;
+    let config = Config::load().map_err(|e| CliError::Run(RunError::Input(e.into())))?;
+    let path = answers_path(flags.get("answers").map(PathBuf::from), &config);
+    let mut answers = Answers::load(&path)?;
+    let rows = verify::verify(&search_path, &answers);
+    println!("{}", verify::format_table(&rows));
+    if flags.switch("record") {
+        let recorded = verify::record_missing(&rows, &mut answers);
+        answers.save(&path)?;
+        println!("recorded {} answers in {}", recorded, path.display());
+    }
+    let failed = rows.iter().filter(|r| r.outcome.is_failure()).count();
+    if failed > 0 {
+        return Err(CliError::Failed(format!(
+            "{} answers did not verify",
+            failed
+        )));
+    }
+    Ok(())
+}
+
+fn bench_command(flags: &Flags) -> Result<(), CliError> {
+    let year = flags.year()?;
+    let day = flags.day()?;
+    let input_name = flags.get("input").unwrap_or("data");
+    let iterations = match flags.get("iterations") {
+        Some(_) => flags.number::<usize>("iterations")?,
+        None => 100,
+    };
+    let solution = runner::lookup(year, day)?;
+    let input = runner::load_input(
+        &flags.search_path()?,
+        year,
+        day,
+        &InputSource::from_arg(input_name),
+    )?;
+    let report = bench::bench(year, day, solution, input_name, &input, iterations)
+        .map_err(|e| CliError::Run(e.into()))?;
+    match flags.get("json") {
+        Some("-") => println!("{}", report.to_json()),
+        Some(path) => {
+            std::fs::write(path, report.to_json() + "\n")
+                .map_err(|e| CliError::Failed(format!("could not write {}: {}", path, e)))?;
+            println!("{}", report.format_table());
+        }
+        None => println!("{}", report.format_table()),
+    }
+    Ok(())
+}
+
+fn fetch_command(flags: &Flags) -> Result<(), CliError> {
+    let year = flags.year()?;
+    let day = flags.day()?;
+    let search_path = flags.search_path()?;
+    let config = Config::load().map_err(|e| CliError::Run(RunError::Input(e.into())))?;
+    let client = AocClient::from_config(&config);
+    match fetch_input(&client, &search_path, year, day, SystemTime::now())? {
+        FetchOutcome::Cached(path) => println!("already have {}", path.display()),
+        FetchOutcome::Downloaded(path) => println!("saved {}", path.display()),
+    }
+    Ok(())
+}
+
+fn submit_command(flags: &Flags) -> Result<(), CliError> {
+    let year = flags.year()?;
+    let day = flags.day()?;
+    let part = flags.part()?;
+    let search_path = flags.search_path()?;
+    let config = Config::load().map_err(|e| CliError::Run(RunError::Input(e.into())))?;
+    let answer = runner::run(year, day, part, &InputSource::Data, &search_path)?.answer;
+    let mut log = SubmissionLog::load(&submit::submissions_path(&config))?;
+    let client = AocClient::from_config(&config);
+    let verdict = submit::submit(
+        &client,
+        &mut log,
+        year,
+        day,
+        part,
+        &answer,
+        SystemTime::now(),
+    )?;
+    println!(
+        "{} day {} part {}: {}, {}",
+        year, day, part, answer, verdict
+    );
+    if verdict == Verdict::Correct {
+        let path = answers_path(None, &config);
+        let mut answers = Answers::load(&path)?;
+        let key = AnswerKey {
+            year,
+            day,
+            part,
+            datafile_type: DataFileType::Data,
+        };
+        answers.insert(key, answer);
+        answers.save(&path)?;
+        println!("recorded the answer in {}", path.display());
+    }
+    Ok(())
+}
+
+/// Parses a comma separated flag value, e.g. `--break 3,jmp`.
+fn list<T: std::str::FromStr<Err = String>>(flags: &Flags, name: &str) -> Result<Vec<T>, CliError> {
+    flags.get(name).map_or(Ok(Vec::new()), |value| {
+        value
+            .split(',')
+            .map(|item| item.trim().parse())
+            .collect::<Result<_, _>>()
+            .map_err(|e| CliError::Usage(format!("--{}: {}", name, e)))
+    })
+}
+
+fn debug_command(flags: &Flags) -> Result<(), CliError> {
+    let year = flags.year()?;
+    let day = flags.day()?;
+    let input = InputSource::from_arg(flags.get("input").unwrap_or("data"));
+    let options = DebugOptions {
+        breakpoints: list(flags, "break")?,
+        watches: list(flags, "watch")?,
+        trace: flags.get("trace").map(PathBuf::from),
+    };
+    for stop in runner::debug(year, day, &input, &flags.search_path()?, &options)? {
+        println!(
+            "{} at pc {}, acc {} after {} steps",
+            stop.stop, stop.pc, stop.acc, stop.steps
+        );
+    }
+    Ok(())
+}
+
+fn new_command(flags: &Flags) -> Result<(), CliError> {
+    let year = flags.year()?;
+    let day = flags.day()?;
+    for path in scaffold::scaffold(&builtin_input_dir(), year, day)? {
+        println!("wrote {}", path.display());
+    }
+    Ok(())
+}
+
+/// Entry point for the command line, `args` excludes the program name.
+pub fn run(args: &[String]) -> Result<(), CliError> {
+    let (command, rest) = args
+        .split_first()
+        .ok_or_else(|| CliError::Usage("missing command".to_string()))?;
+    let flags = Flags::parse(rest)?;
+    match command.as_str() {
+        "run" => run_command(&flags),
+        "verify" => verify_command(&flags),
+        "bench" => bench_command(&flags),
+        "fetch" => fetch_command(&flags),
+        "submit" => submit_command(&flags),
+        "new" => new_command(&flags),
+        "debug" => debug_command(&flags),
+        _ => Err(CliError::Usage(format!("unknown command {:?}", command))),
+    }
+}
+
+#[cfg(test)]
+mod cli_tests {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_flags_parse() {
+        let flags = Flags::parse(&args("--year 2020 --day 8")).unwrap();
+        assert_eq!(flags.get("year"), Some("2020"));
+        assert_eq!(flags.number::<u8>("day"), Ok(8));
+        assert_eq!(flags.get("part"), None);
+    }
+
+    #[test]
+    fn test_flags_switch() {
+        let flags = Flags::parse(&args("--record --answers a.txt")).unwrap();
+        assert!(flags.switch("record"));
+        assert_eq!(flags.get("answers"), Some("a.txt"));
+        assert!(!Flags::parse(&[]).unwrap().switch("record"));
+    }
+
+    #[test]
+    fn test_flags_missing_value() {
+        let result = Flags::parse(&args("--year"));
+        assert_eq!(
+            result,
+            Err(CliError::Usage("missing value for --year".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_run_unknown_day() {
+        let result = run(&args("run --year 2020 --day 3 --part 1 --input sample"));
+        assert_eq!(
+            result,
+            Err(CliError::Run(RunError::UnknownDay(
+                AocYear::Aoc2020,
+                AocDay::Day3
+            )))
+        );
+    }
+
+    #[test]
+    fn test_run_rejects_bad_year_and_part() {
+        let result = run(&args("run --year 1999 --day 1 --part 1"));
+        assert!(matches!(result, Err(CliError::Usage(_))));
+        let result = run(&args("run --year 2020 --day 1 --part 3"));
+        assert!(matches!(result, Err(CliError::Usage(_))));
+    }
+
+    #[test]
+    fn test_unknown_command() {
+        let result = run(&args("explode"));
+        assert!(matches!(result, Err(CliError::Usage(_))));
+    }
+}
@@ -0,0 +1,183 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Name of the project config file, looked up from the current directory upwards.
+pub const CONFIG_FILE_NAME: &str = "aoc.toml";
+/// Environment variable naming a config file to use instead of searching for one.
+pub const CONFIG_ENV: &str = "AOC_CONFIG";
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ConfigError {
+    Io(PathBuf, String),
+    Syntax {
+        path: Option<PathBuf>,
+        line: usize,
+        text: String,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(path, reason) => {
+                write!(f, "could not read config {}: {}", path.display(), reason)
+            }
+            ConfigError::Syntax { path, line, text } => write!(
+                f,
+                "{}:{}: expected `key = value`, got {:?}",
+                path.as_ref()
+                    .map_or(CONFIG_FILE_NAME.into(), |p| p.display().to_string()),
+                line,
+                text
+            ),
+        }
+    }
+}
+
+/// `line` up to a `#` outside double quotes, `None` when a quote is left open.
+fn strip_comment(line: &str) -> Option<&str> {
+    let mut quoted = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '#' if !quoted => return Some(&line[..i]),
+            _ => {}
+        }
+    }
+    (!quoted).then_some(line)
+}
+
+/// Settings from `aoc.toml`: flat `key = "value"` lines, `#` outside quotes starts a
+/// comment.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Config {
+    path: Option<PathBuf>,
+    values: Vec<(String, String)>,
+}
+
+impl Config {
+    pub fn parse(path: Option<PathBuf>, text: &str) -> Result<Self, ConfigError> {
+        let mut values = Vec::new();
+        for (n, line) in text.lines().enumerate() {
+            let syntax = || ConfigError::Syntax {
+                path: path.clone(),
+                line: n + 1,
+                text: line.to_string(),
+            };
+            let content = strip_comment(line).ok_or_else(syntax)?.trim();
+            if content.is_empty() {
+                continue;
+            }
+            let (key, value) = content
+                .split_once('=')
+                .map(|(k, v)| (k.trim(), v.trim()))
+                .filter(|(k, _)| !k.is_empty())
+                .ok_or_else(syntax)?;
+            let value = value
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .unwrap_or(value);
+            values.push((key.to_string(), value.to_string()));
+        }
+        Ok(Self { path, values })
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| ConfigError::Io(path.to_path_buf(), e.to_string()))?;
+        Self::parse(Some(path.to_path_buf()), &text)
+    }
+
+    /// Loads `$AOC_CONFIG`, or the nearest `aoc.toml`, or an empty config.
+    pub fn load() -> Result<Self, ConfigError> {
+        if let Some(path) = std::env::var_os(CONFIG_ENV) {
+            return Self::from_file(Path::new(&path));
+        }
+        let cwd = std::env::current_dir().unwrap_or_default();
+        match cwd
+            .ancestors()
+            .map(|dir| dir.join(CONFIG_FILE_NAME))
+            .find(|path| path.is_file())
+        {
+            Some(path) => Self::from_file(&path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values
+            .iter()
+            .rev()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// A path setting, relative paths are taken from the config file's directory.
+    pub fn get_path(&self, key: &str) -> Option<PathBuf> {
+        let value = PathBuf::from(self.get(key)?);
+        match self.path.as_deref().and_then(Path::parent) {
+            Some(dir) if value.is_relative() => Some(dir.join(value)),
+            _ => Some(value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod config_tests {
+    use super::*;
+
+    const CONFIG: &str = "\
+# personal inputs live outside the repo
+input_dir = \"inputs\"
+session=abc123  # trailing comment
+";
+
+    #[test]
+    fn test_parse_values() {
+        let config = Config::parse(None, CONFIG).unwrap();
+        assert_eq!(config.get("input_dir"), Some("inputs"));
+        assert_eq!(config.get("session"), Some("abc123"));
+        assert_eq!(config.get("missing"), None);
+    }
+
+    #[test]
+    fn test_relative_path_is_taken_from_config_dir() {
+        let config = Config::parse(Some(PathBuf::from("/home/elf/aoc.toml")), CONFIG).unwrap();
+        assert_eq!(
+            config.get_path("input_dir"),
+            Some(PathBuf::from("/home/elf/inputs"))
+        );
+    }
+
+    #[test]
+    fn test_syntax_error_reports_line() {
+        let result = Config::parse(None, "input_dir = x\nnonsense\n");
+        assert_eq!(
+            result,
+            Err(ConfigError::Syntax {
+                path: None,
+                line: 2,
+                text: "nonsense".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_hash_inside_quotes_is_kept() {
+        let config = Config::parse(None, "input_dir = \"inputs#2\" # comment\n").unwrap();
+        assert_eq!(config.get("input_dir"), Some("inputs#2"));
+        let result = Config::parse(None, "session = \"abc#123\n");
+        assert_eq!(
+            result,
+            Err(ConfigError::Syntax {
+                path: None,
+                line: 1,
+                text: "session = \"abc#123".to_string()
+            })
+        );
+    }
+}
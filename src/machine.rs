@@ -0,0 +1,572 @@
+use crate::aoc_common::InputParsable;
+use nom::character::complete::space0;
+use nom::combinator::all_consuming;
+use nom::sequence::delimited;
+use std::fmt;
+use std::io::Write;
+
+/// A small instruction machine: a puzzle supplies its opcodes, its register file and
+/// what each instruction does, [`Vm`] runs programs for it.
+pub trait Machine {
+    type Instruction: InputParsable + Copy + fmt::Debug + fmt::Display;
+    type Registers: Clone + Default + PartialEq + fmt::Debug + fmt::Display;
+
+    /// Applies `instruction` to the registers and says where the pc goes next.
+    fn execute(&self, instruction: Self::Instruction, registers: &mut Self::Registers) -> Flow;
+
+    /// Clock cycles the instruction takes.
+    fn cycles(&self, _instruction: Self::Instruction) -> u64 {
+        1
+    }
+
+    /// Moves the pc by `offset`, `None` when the machine's pc cannot hold the result.
+    fn advance(&self, pc: i64, offset: i64) -> Option<i64> {
+        pc.checked_add(offset)
+    }
+}
+
+/// Where the program counter goes after an instruction.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum Flow {
+    Next,
+    /// Relative to the instruction that jumped.
+    Jump(i64),
+    /// A register could not hold the result.
+    Overflow,
+}
+
+/// Why a [`Vm`] stopped.
+#[derive(PartialEq, Debug, Clone)]
+pub enum Halt {
+    /// The pc moved exactly one past the last instruction.
+    Terminated,
+    /// The instruction at `pc` was about to run a second time.
+    InfiniteLoop {
+        pc: i64,
+    },
+    /// The pc moved further past the end of the program.
+    PcOutOfRange {
+        pc: i64,
+    },
+    /// The pc moved before the first instruction.
+    NegativePc {
+        pc: i64,
+    },
+    /// The instruction at `pc` overflowed a register or the pc, nothing was changed.
+    Overflow {
+        pc: i64,
+    },
+    StepLimitReached {
+        steps: usize,
+    },
+    /// Writing the trace of the instruction at `pc` failed, nothing was changed.
+    Trace(String),
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct MachineState<R> {
+    pub pc: i64,
+    pub registers: R,
+    pub steps: usize,
+    pub cycles: u64,
+}
+
+/// What undoes one executed instruction: the pc it ran at and the registers before
+/// it, kept only when it changed them.
+#[derive(PartialEq, Debug, Clone)]
+struct Undo<R> {
+    pc: i64,
+    registers: Option<R>,
+}
+
+/// Runs a program for machine `M` one instruction at a time.
+pub struct Vm<'a, M: Machine> {
+    machine: M,
+    program: &'a [M::Instruction],
+    state: MachineState<M::Registers>,
+    visited: Option<Vec<bool>>,
+    step_limit: Option<usize>,
+    trace: Option<Box<dyn Write + 'a>>,
+    history: Option<Vec<Undo<M::Registers>>>,
+}
+
+#[allow(dead_code)]
+impl<'a, M: Machine> Vm<'a, M> {
+    /// A machine at pc 0 with default registers that stops before any instruction
+    /// runs twice.
+    pub fn new(program: &'a [M::Instruction]) -> Self
+    where
+        M: Default,
+    {
+        Self {
+            machine: M::default(),
+            program,
+            state: MachineState {
+                pc: 0,
+                registers: M::Registers::default(),
+                steps: 0,
+                cycles: 0,
+            },
+            visited: Some(vec![false; program.len()]),
+            step_limit: None,
+            trace: None,
+            history: None,
+        }
+    }
+
+    /// Swaps in a configured machine.
+    pub fn with_machine(self, machine: M) -> Self {
+        Self { machine, ..self }
+    }
+
+    pub fn with_registers(self, registers: M::Registers) -> Self {
+        Self {
+            state: MachineState {
+                registers,
+                ..self.state
+            },
+            ..self
+        }
+    }
+
+    /// For machines whose registers change what an instruction does, so a repeated
+    /// pc is not a loop.
+    pub fn without_loop_detection(self) -> Self {
+        Self {
+            visited: None,
+            ..self
+        }
+    }
+
+    /// Stops with [`Halt::StepLimitReached`] after `limit` instructions.
+    pub fn with_step_limit(self, limit: usize) -> Self {
+        Self {
+            step_limit: Some(limit),
+            ..self
+        }
+    }
+
+    /// Records an undo log so execution can be stepped backwards.
+    pub fn with_history(self) -> Self {
+        Self {
+            history: Some(Vec::new()),
+            ..self
+        }
+    }
+
+    /// Writes `pc instruction registers_before registers_after` per executed instruction.
+    pub fn trace_to(&mut self, out: impl Write + 'a) {
+        self.trace = Some(Box::new(out));
+    }
+
+    pub fn state(&self) -> &MachineState<M::Registers> {
+        &self.state
+    }
+
+    pub fn registers(&self) -> &M::Registers {
+        &self.state.registers
+    }
+
+    pub fn pc(&self) -> i64 {
+        self.state.pc
+    }
+
+    /// The instruction at the pc, `None` outside the program.
+    pub fn current_instruction(&self) -> Option<M::Instruction> {
+        let index = usize::try_from(self.state.pc).ok()?;
+        self.program.get(index).copied()
+    }
+
+    /// Executes one instruction, or returns why it cannot.
+    pub fn step(&mut self) -> Option<Halt> {
+        let pc = self.state.pc;
+        let index = match usize::try_from(pc) {
+            Ok(i) if i == self.program.len() => return Some(Halt::Terminated),
+            Ok(i) if i < self.program.len() => i,
+            Ok(_) => return Some(Halt::PcOutOfRange { pc }),
+            Err(_) => return Some(Halt::NegativePc { pc }),
+        };
+        if self.visited.as_ref().is_some_and(|visited| visited[index]) {
+            return Some(Halt::InfiniteLoop { pc });
+        }
+        if self.step_limit == Some(self.state.steps) {
+            return Some(Halt::StepLimitReached {
+                steps: self.state.steps,
+            });
+        }
+        let instruction = self.program[index];
+        let mut registers = self.state.registers.clone();
+        let next = match self.machine.execute(instruction, &mut registers) {
+            Flow::Next => self.machine.advance(pc, 1),
+            Flow::Jump(offset) => self.machine.advance(pc, offset),
+            Flow::Overflow => None,
+        };
+        let Some(next) = next else {
+            return Some(Halt::Overflow { pc });
+        };
+        // traced before anything changes so a failed write leaves the state as it was
+        if let Some(out) = &mut self.trace {
+            let line = format!(
+                "{} {} {} {}",
+                pc, instruction, self.state.registers, registers
+            );
+            if let Err(e) = writeln!(out, "{}", line) {
+                return Some(Halt::Trace(e.to_string()));
+            }
+        }
+        let before = std::mem::replace(&mut self.state.registers, registers);
+        if let Some(visited) = &mut self.visited {
+            visited[index] = true;
+        }
+        if let Some(history) = &mut self.history {
+            let changed = before != self.state.registers;
+            history.push(Undo {
+                pc,
+                registers: changed.then_some(before),
+            });
+        }
+        self.state.pc = next;
+        self.state.steps += 1;
+        self.state.cycles += self.machine.cycles(instruction);
+        None
+    }
+
+    pub fn run(&mut self) -> Halt {
+        loop {
+            if let Some(halt) = self.step() {
+                return halt;
+            }
+        }
+    }
+
+    /// Undoes the last instruction, `false` at the start or without history.
+    pub fn step_back(&mut self) -> bool {
+        let Some(undo) = self.history.as_mut().and_then(Vec::pop) else {
+            return false;
+        };
+        let index = undo.pc as usize;
+        if let Some(registers) = undo.registers {
+            self.state.registers = registers;
+        }
+        if let Some(visited) = &mut self.visited {
+            visited[index] = false;
+        }
+        self.state.pc = undo.pc;
+        self.state.steps -= 1;
+        self.state.cycles -= self.machine.cycles(self.program[index]);
+        true
+    }
+
+    /// Moves to just before instruction `step` (counting from 0) runs, backwards through
+    /// the history or forwards by executing; returns the halt when it comes first.
+    pub fn goto(&mut self, step: usize) -> Option<Halt> {
+        while self.state.steps > step {
+            if !self.step_back() {
+                return None;
+            }
+        }
+        while self.state.steps < step {
+            if let Some(halt) = self.step() {
+                return Some(halt);
+            }
+        }
+        None
+    }
+
+    /// The latest recorded step whose instruction changed the registers.
+    pub fn last_register_change(&self) -> Option<usize> {
+        self.history
+            .as_ref()?
+            .iter()
+            .rposition(|undo| undo.registers.is_some())
+    }
+
+    /// The first recorded step that ran the instruction at `pc`.
+    pub fn first_visit(&self, pc: i64) -> Option<usize> {
+        self.history.as_ref()?.iter().position(|undo| undo.pc == pc)
+    }
+}
+
+/// Where a program line stopped making sense, `token` is empty at the end of a line.
+#[derive(PartialEq, Debug, Clone)]
+pub struct ProgramParseError {
+    pub line: usize,
+    pub column: usize,
+    pub token: String,
+}
+
+impl fmt::Display for ProgramParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+        if self.token.is_empty() {
+            write!(f, "unexpected end of line")
+        } else {
+            write!(f, "unexpected {:?}", self.token)
+        }
+    }
+}
+
+/// Parses one line holding a single instruction, surrounding spaces allowed.
+pub fn parse_line<I: InputParsable>(
+    line_number: usize,
+    line: &str,
+) -> Result<I, ProgramParseError> {
+    let parsed = all_consuming(delimited(space0, I::parse, space0))(line);
+    parsed.map(|(_, instruction)| instruction).map_err(|e| {
+        let rest = match e {
+            nom::Err::Error(e) | nom::Err::Failure(e) => e.input,
+            nom::Err::Incomplete(_) => "",
+        };
+        let token = rest.trim_start();
+        ProgramParseError {
+            line: line_number,
+            column: line.len() - token.len() + 1,
+            token: token
+                .split_whitespace()
+                .next()
+                .unwrap_or_default()
+                .to_string(),
+        }
+    })
+}
+
+/// Parses one instruction per line, blank lines are skipped.
+pub fn parse_program<I: InputParsable>(s: &str) -> Result<Vec<I>, ProgramParseError> {
+    s.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(n, line)| parse_line(n + 1, line))
+        .collect()
+}
+
+#[cfg(test)]
+mod machine_tests {
+    use super::*;
+    use nom::branch::alt;
+    use nom::bytes::complete::tag;
+    use nom::character::complete::{i64 as parse_i64, space1};
+    use nom::combinator::{map, value};
+    use nom::sequence::preceded;
+    use nom::IResult;
+
+    /// The 2022 day 10 CRT: `addx` takes two cycles, `noop` one.
+    #[derive(PartialEq, Debug, Copy, Clone)]
+    enum Crt {
+        Noop,
+        Addx(i64),
+    }
+
+    impl fmt::Display for Crt {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Crt::Noop => write!(f, "noop"),
+                Crt::Addx(v) => write!(f, "addx {}", v),
+            }
+        }
+    }
+
+    impl InputParsable for Crt {
+        fn parse(input: &str) -> IResult<&str, Self> {
+            alt((
+                value(Crt::Noop, tag("noop")),
+                map(
+                    preceded(tag("addx"), preceded(space1, parse_i64)),
+                    Crt::Addx,
+                ),
+            ))(input)
+        }
+    }
+
+    #[derive(PartialEq, Debug, Clone)]
+    struct X(i64);
+
+    impl Default for X {
+        fn default() -> Self {
+            X(1)
+        }
+    }
+
+    impl fmt::Display for X {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{}", self.0)
+        }
+    }
+
+    #[derive(Default)]
+    struct CrtMachine;
+
+    impl Machine for CrtMachine {
+        type Instruction = Crt;
+        type Registers = X;
+
+        fn execute(&self, instruction: Crt, x: &mut X) -> Flow {
+            match instruction {
+                Crt::Noop => Flow::Next,
+                Crt::Addx(v) => match x.0.checked_add(v) {
+                    Some(sum) => {
+                        x.0 = sum;
+                        Flow::Next
+                    }
+                    None => Flow::Overflow,
+                },
+            }
+        }
+
+        fn cycles(&self, instruction: Crt) -> u64 {
+            match instruction {
+                Crt::Noop => 1,
+                Crt::Addx(_) => 2,
+            }
+        }
+    }
+
+    /// A machine that only jumps, to exercise loop detection and the pc checks.
+    #[derive(Default)]
+    struct JumpMachine;
+
+    impl Machine for JumpMachine {
+        type Instruction = Crt;
+        type Registers = X;
+
+        fn execute(&self, instruction: Crt, _: &mut X) -> Flow {
+            match instruction {
+                Crt::Noop => Flow::Next,
+                Crt::Addx(v) => Flow::Jump(v),
+            }
+        }
+    }
+
+    #[test]
+    fn test_run_counts_cycles_and_traces() {
+        let program: Vec<Crt> = parse_program("noop\naddx 3\naddx -5\n").unwrap();
+        let mut trace = Vec::new();
+        let mut vm = Vm::<CrtMachine>::new(&program);
+        vm.trace_to(&mut trace);
+        assert_eq!(vm.run(), Halt::Terminated);
+        assert_eq!(
+            vm.state().clone(),
+            MachineState {
+                pc: 3,
+                registers: X(-1),
+                steps: 3,
+                cycles: 5
+            }
+        );
+        drop(vm);
+        assert_eq!(
+            String::from_utf8(trace).unwrap(),
+            "0 noop 1 1\n1 addx 3 1 4\n2 addx -5 4 -1\n"
+        );
+    }
+
+    /// A trace destination that always fails.
+    struct Broken;
+
+    impl Write for Broken {
+        fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::other("disk full"))
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_failed_trace_changes_nothing() {
+        let program = [Crt::Addx(3)];
+        let mut vm = Vm::<CrtMachine>::new(&program).with_history();
+        vm.trace_to(Broken);
+        assert_eq!(vm.step(), Some(Halt::Trace("disk full".to_string())));
+        assert_eq!(
+            vm.state().clone(),
+            MachineState {
+                pc: 0,
+                registers: X(1),
+                steps: 0,
+                cycles: 0
+            }
+        );
+        assert!(!vm.step_back());
+        assert_eq!(vm.step(), Some(Halt::Trace("disk full".to_string())));
+    }
+
+    #[test]
+    fn test_halts() {
+        let program = [Crt::Noop, Crt::Addx(-1)];
+        assert_eq!(
+            Vm::<JumpMachine>::new(&program).run(),
+            Halt::InfiniteLoop { pc: 0 }
+        );
+        let mut vm = Vm::<JumpMachine>::new(&program)
+            .without_loop_detection()
+            .with_step_limit(10);
+        assert_eq!(vm.run(), Halt::StepLimitReached { steps: 10 });
+        assert_eq!(
+            Vm::<JumpMachine>::new(&[Crt::Addx(-2)]).run(),
+            Halt::NegativePc { pc: -2 }
+        );
+        assert_eq!(
+            Vm::<JumpMachine>::new(&[Crt::Addx(3)]).run(),
+            Halt::PcOutOfRange { pc: 3 }
+        );
+        let overflowing = [Crt::Noop, Crt::Addx(i64::MAX)];
+        let mut vm = Vm::<CrtMachine>::new(&overflowing);
+        assert_eq!(vm.run(), Halt::Overflow { pc: 1 });
+        assert_eq!((vm.pc(), vm.registers(), vm.state().steps), (1, &X(1), 1));
+        assert_eq!(
+            Vm::<JumpMachine>::new(&overflowing).run(),
+            Halt::Overflow { pc: 1 }
+        );
+        let vm = Vm::<CrtMachine>::new(&program).with_registers(X(7));
+        assert_eq!(vm.registers(), &X(7));
+    }
+
+    #[test]
+    fn test_history() {
+        let program: Vec<Crt> = parse_program("noop\naddx 3\nnoop\naddx -5\n").unwrap();
+        let mut vm = Vm::<CrtMachine>::new(&program).with_history();
+        assert!(!vm.step_back());
+        assert_eq!(vm.run(), Halt::Terminated);
+        assert_eq!((vm.registers(), vm.state().cycles), (&X(-1), 6));
+        assert_eq!(vm.last_register_change(), Some(3));
+        assert_eq!(vm.first_visit(2), Some(2));
+        assert_eq!(vm.first_visit(9), None);
+
+        assert!(vm.step_back());
+        assert_eq!((vm.pc(), vm.registers(), vm.state().cycles), (3, &X(4), 4));
+        assert_eq!(vm.last_register_change(), Some(1));
+        assert_eq!(vm.goto(1), None);
+        assert_eq!(
+            vm.state().clone(),
+            MachineState {
+                pc: 1,
+                registers: X(1),
+                steps: 1,
+                cycles: 1
+            }
+        );
+        assert_eq!(vm.goto(10), Some(Halt::Terminated));
+        assert_eq!(vm.registers(), &X(-1));
+
+        let looping = [Crt::Noop, Crt::Addx(-1)];
+        let mut vm = Vm::<JumpMachine>::new(&looping).with_history();
+        assert_eq!(vm.run(), Halt::InfiniteLoop { pc: 0 });
+        assert_eq!(vm.goto(0), None);
+        assert_eq!(vm.run(), Halt::InfiniteLoop { pc: 0 });
+        assert!(!Vm::<JumpMachine>::new(&looping).step_back());
+    }
+
+    #[test]
+    fn test_parse_program_reports_position() {
+        let result = parse_program::<Crt>("noop\naddx x\n");
+        assert_eq!(
+            result,
+            Err(ProgramParseError {
+                line: 2,
+                column: 6,
+                token: "x".to_string()
+            })
+        );
+    }
+}
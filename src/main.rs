@@ -1,14 +1,35 @@
 mod aoc2020 {
-    mod aoc1;
-    mod day1_problems;
-    mod day5_problems;
-    mod day8_problems;
+    pub mod day1_problems;
+    pub mod day5_problems;
+    pub mod day8_problems;
 }
 
 mod aoc2022 {
-    mod day11_problems;
+    pub mod day11_problems;
 }
 
-fn main() {
-    println!("Hello, world!");
+mod answers;
+mod aoc_client;
+mod aoc_common;
+mod bench;
+mod cli;
+mod config;
+mod machine;
+mod runner;
+mod scaffold;
+mod solution;
+mod submit;
+mod verify;
+
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match cli::run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
 }
@@ -0,0 +1,374 @@
+use crate::aoc2020::day8_problems::cpu::Cpu;
+use crate::aoc2020::day8_problems::day8parsing::parse_program;
+use crate::aoc2020::day8_problems::debugger::{Breakpoint, Debugger, Stop, Watch};
+use crate::aoc_common::file::load_input_lines_from;
+use crate::aoc_common::*;
+use crate::solution::{find_solution, registry, Answer, DynSolution, Part, SolutionError};
+use std::fmt;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum InputSource {
+    Sample,
+    Data,
+    Path(PathBuf),
+}
+
+impl InputSource {
+    pub fn from_arg(s: &str) -> Self {
+        match s {
+            "sample" => InputSource::Sample,
+            "data" => InputSource::Data,
+            path => InputSource::Path(PathBuf::from(path)),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum RunError {
+    UnknownYear(AocYear),
+    UnknownDay(AocYear, AocDay),
+    UnknownPart(AocYear, AocDay, Part),
+    InputNotFound(PathBuf),
+    Input(AocInputError),
+    BadInput(String),
+    NoAnswer(String),
+    NotDebuggable(AocYear, AocDay),
+    Trace(PathBuf, String),
+}
+
+impl fmt::Display for RunError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RunError::UnknownYear(year) => write!(f, "no solutions for year {}", year),
+            RunError::UnknownDay(year, day) => {
+                write!(f, "no solution for {} day {}", year, day)
+            }
+            RunError::UnknownPart(year, day, part) => {
+                write!(f, "no solution for {} day {} part {}", year, day, part)
+            }
+            RunError::InputNotFound(path) => {
+                write!(f, "input file not found: {}", path.display())
+            }
+            RunError::Input(e) => write!(f, "{}", e),
+            RunError::BadInput(reason) => write!(f, "bad input: {}", reason),
+            RunError::NoAnswer(reason) => write!(f, "no answer: {}", reason),
+            RunError::NotDebuggable(year, day) => {
+                write!(f, "{} day {} is not a VM puzzle", year, day)
+            }
+            RunError::Trace(path, reason) => {
+                write!(f, "could not write trace {}: {}", path.display(), reason)
+            }
+        }
+    }
+}
+
+impl From<SolutionError> for RunError {
+    fn from(e: SolutionError) -> Self {
+        match e {
+            SolutionError::Parse(reason) => RunError::BadInput(reason),
+            SolutionError::NoAnswer(reason) => RunError::NoAnswer(reason),
+            SolutionError::NotImplemented => RunError::NoAnswer("not implemented".to_string()),
+            SolutionError::MismatchedInput => RunError::BadInput(e.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct RunResult {
+    pub answer: Answer,
+    pub elapsed: Duration,
+}
+
+pub fn load_input(
+    search_path: &InputSearchPath,
+    year: AocYear,
+    day: AocDay,
+    input: &InputSource,
+) -> Result<String, RunError> {
+    let datafile_type = match input {
+        InputSource::Path(path) if !path.exists() => {
+            return Err(RunError::InputNotFound(path.clone()))
+        }
+        InputSource::Path(path) => return read_input_file(path).map_err(RunError::Input),
+        InputSource::Sample => DataFileType::SampleData,
+        InputSource::Data => DataFileType::Data,
+    };
+    load_input_lines_from(search_path, year, day, datafile_type).map_err(RunError::Input)
+}
+
+pub fn lookup(year: AocYear, day: AocDay) -> Result<&'static dyn DynSolution, RunError> {
+    find_solution(year, day).ok_or_else(|| {
+        if registry().iter().any(|r| r.year == year) {
+            RunError::UnknownDay(year, day)
+        } else {
+            RunError::UnknownYear(year)
+        }
+    })
+}
+
+/// Runs the solver for `year`/`day`/`part` against already loaded input.
+pub fn solve(year: AocYear, day: AocDay, part: Part, input: &str) -> Result<Answer, RunError> {
+    let solution = lookup(year, day)?;
+    if input.trim().is_empty() {
+        return Err(RunError::BadInput("empty input".to_string()));
+    }
+    solution
+        .solve(part, input)
+        .map_err(|e| solve_error(year, day, part, e))
+}
+
+fn solve_error(year: AocYear, day: AocDay, part: Part, e: SolutionError) -> RunError {
+    match e {
+        SolutionError::NotImplemented => RunError::UnknownPart(year, day, part),
+        e => e.into(),
+    }
+}
+
+/// Loads the input, runs the solver and times it.
+pub fn run(
+    year: AocYear,
+    day: AocDay,
+    part: Part,
+    input: &InputSource,
+    search_path: &InputSearchPath,
+) -> Result<RunResult, RunError> {
+    lookup(year, day)?;
+    let input = load_input(search_path, year, day, input)?;
+    let start = Instant::now();
+    let answer = solve(year, day, part, &input)?;
+    Ok(RunResult {
+        answer,
+        elapsed: start.elapsed(),
+    })
+}
+
+/// What to stop on while debugging a VM puzzle.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct DebugOptions {
+    pub breakpoints: Vec<Breakpoint>,
+    pub watches: Vec<Watch>,
+    pub trace: Option<PathBuf>,
+}
+
+/// A stop of a debugging run and the machine state at that point.
+#[derive(Debug, PartialEq)]
+pub struct DebugStop {
+    pub stop: Stop,
+    pub pc: i32,
+    pub acc: i32,
+    pub steps: usize,
+}
+
+/// Runs a VM puzzle's program under the debugger, collecting every stop until it halts.
+pub fn debug(
+    year: AocYear,
+    day: AocDay,
+    input: &InputSource,
+    search_path: &InputSearchPath,
+    options: &DebugOptions,
+) -> Result<Vec<DebugStop>, RunError> {
+    lookup(year, day)?;
+    if (year, day) != (AocYear::Aoc2020, AocDay::Day8) {
+        return Err(RunError::NotDebuggable(year, day));
+    }
+    let program = parse_program(&load_input(search_path, year, day, input)?)
+        .map_err(|e| RunError::BadInput(e.to_string()))?;
+    let trace_error =
+        |path: &PathBuf, e: std::io::Error| RunError::Trace(path.clone(), e.to_string());
+    let mut trace = match &options.trace {
+        Some(path) => Some(BufWriter::new(
+            std::fs::File::create(path).map_err(|e| trace_error(path, e))?,
+        )),
+        None => None,
+    };
+
+    let mut debugger = Debugger::new(Cpu::new(&program));
+    options
+        .breakpoints
+        .iter()
+        .for_each(|b| debugger.add_breakpoint(*b));
+    options.watches.iter().for_each(|w| debugger.add_watch(*w));
+    if let Some(out) = &mut trace {
+        debugger.trace_to(out);
+    }
+    let mut stops = Vec::new();
+    let mut result = debugger.start();
+    while let Ok(stop) = &result {
+        let cpu = debugger.cpu();
+        stops.push(DebugStop {
+            stop: stop.clone(),
+            pc: cpu.pc().into(),
+            acc: cpu.acc().into(),
+            steps: cpu.steps(),
+        });
+        if let Stop::Halted(_) = stop {
+            break;
+        }
+        result = debugger.resume();
+    }
+    drop(debugger);
+    if let (Some(path), Some(out)) = (&options.trace, &mut trace) {
+        result
+            .and_then(|_| out.flush())
+            .map_err(|e| trace_error(path, e))?;
+    }
+    Ok(stops)
+}
+
+#[cfg(test)]
+mod runner_tests {
+    use super::*;
+    use crate::config::Config;
+
+    fn builtin() -> InputSearchPath {
+        InputSearchPath::new(None, None, &Config::default())
+    }
+
+    const ONE_MONKEY: &str = "Monkey 0:\n  Starting items: 1\n  Operation: new = old + 6\n  Test: divisible by 19\n    If true: throw to monkey 0\n    If false: throw to monkey 0\n";
+
+    const DAY8_SAMPLE: &str =
+        "nop +0\nacc +1\njmp +4\nacc +3\njmp -3\nacc -99\nacc +1\njmp -4\nacc +6\n";
+
+    #[test]
+    fn test_solve_day8_sample() {
+        assert_eq!(
+            solve(AocYear::Aoc2020, AocDay::Day8, Part::One, DAY8_SAMPLE),
+            Ok(Answer::Number(5))
+        );
+    }
+
+    #[test]
+    fn test_solve_unknown_combinations() {
+        use AocDay::*;
+        use AocYear::*;
+        assert_eq!(
+            solve(Aoc2019, Day1, Part::One, ""),
+            Err(RunError::UnknownYear(Aoc2019))
+        );
+        assert_eq!(
+            solve(Aoc2020, Day2, Part::One, ""),
+            Err(RunError::UnknownDay(Aoc2020, Day2))
+        );
+        assert_eq!(
+            solve_error(Aoc2022, Day11, Part::Two, SolutionError::NotImplemented),
+            RunError::UnknownPart(Aoc2022, Day11, Part::Two)
+        );
+    }
+
+    #[test]
+    fn test_solve_rejects_invalid_monkeys() {
+        assert_eq!(
+            solve(AocYear::Aoc2022, AocDay::Day11, Part::Two, ONE_MONKEY),
+            Err(RunError::BadInput("monkey 0: throws to itself".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_solve_empty_input() {
+        let result = solve(AocYear::Aoc2020, AocDay::Day1, Part::One, "\n");
+        assert_eq!(result, Err(RunError::BadInput("empty input".to_string())));
+    }
+
+    #[test]
+    fn test_run_with_sample_file() {
+        let result = run(
+            AocYear::Aoc2020,
+            AocDay::Day5,
+            Part::One,
+            &InputSource::Sample,
+            &builtin(),
+        )
+        .unwrap();
+        assert_eq!(result.answer, Answer::Number(820));
+    }
+
+    #[test]
+    fn test_run_with_data_file() {
+        let result = run(
+            AocYear::Aoc2020,
+            AocDay::Day1,
+            Part::One,
+            &InputSource::Data,
+            &builtin(),
+        )
+        .unwrap();
+        assert_eq!(result.answer, Answer::Number(877971));
+    }
+
+    #[test]
+    fn test_run_missing_path() {
+        let path = PathBuf::from("does/not/exist.txt");
+        let result = run(
+            AocYear::Aoc2020,
+            AocDay::Day8,
+            Part::One,
+            &InputSource::Path(path.clone()),
+            &builtin(),
+        );
+        assert_eq!(result, Err(RunError::InputNotFound(path)));
+    }
+
+    #[test]
+    fn test_run_reports_missing_data_file() {
+        let search_path = InputSearchPath::new(
+            Some(PathBuf::from("/no/such/dir")),
+            None,
+            &Config::default(),
+        );
+        let result = run(
+            AocYear::Aoc2022,
+            AocDay::Day11,
+            Part::One,
+            &InputSource::Data,
+            &search_path,
+        );
+        match result {
+            Err(RunError::Input(AocInputError::NotFound { tried, .. })) => {
+                assert_eq!(tried.len(), 2)
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_debug_writes_trace_and_stops() {
+        let trace = std::env::temp_dir().join(format!("aoc-trace-{}.txt", std::process::id()));
+        let options = DebugOptions {
+            breakpoints: vec!["acc".parse().unwrap()],
+            watches: Vec::new(),
+            trace: Some(trace.clone()),
+        };
+        let stops = debug(
+            AocYear::Aoc2020,
+            AocDay::Day8,
+            &InputSource::Sample,
+            &builtin(),
+            &options,
+        )
+        .unwrap();
+        let pcs: Vec<_> = stops.iter().map(|s| s.pc).collect();
+        assert_eq!(pcs, vec![1, 6, 3, 1, 1]);
+        assert!(matches!(stops.last().unwrap().stop, Stop::Halted(_)));
+        let written = std::fs::read_to_string(&trace).unwrap();
+        assert_eq!(written.lines().count(), 7);
+        std::fs::remove_file(trace).unwrap();
+    }
+
+    #[test]
+    fn test_debug_needs_vm_puzzle() {
+        let result = debug(
+            AocYear::Aoc2020,
+            AocDay::Day5,
+            &InputSource::Sample,
+            &builtin(),
+            &DebugOptions::default(),
+        );
+        assert_eq!(
+            result,
+            Err(RunError::NotDebuggable(AocYear::Aoc2020, AocDay::Day5))
+        );
+    }
+}
@@ -0,0 +1,398 @@
+use crate::aoc_common::{data_file_relative_path, AocDay, AocYear, DataFileType};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+const DAY_TEMPLATE: &str = r#"use crate::aoc_common::InputParsable;
+use crate::solution::{Answer, Solution, SolutionError};
+
+#[allow(dead_code)]
+pub mod day{day} {
+    use crate::aoc_common::InputParsable;
+    use nom::IResult;
+
+    #[derive(Debug, PartialEq, Clone)]
+    pub struct Puzzle(pub Vec<String>);
+
+    impl InputParsable for Puzzle {
+        fn parse(input: &str) -> IResult<&str, Self> {
+            // replace with a nom parser for the puzzle input
+            Ok(("", Self(input.lines().map(str::to_string).collect())))
+        }
+    }
+}
+
+pub struct Day{day}Solution;
+
+impl Solution for Day{day}Solution {
+    type Input = day{day}::Puzzle;
+
+    fn parse(&self, input: &str) -> Result<day{day}::Puzzle, SolutionError> {
+        match day{day}::Puzzle::parse(input) {
+            Ok((rest, puzzle)) if rest.trim().is_empty() => Ok(puzzle),
+            Ok((rest, _)) => Err(SolutionError::Parse(format!(
+                "unexpected input {:?}",
+                rest.lines().next().unwrap_or_default()
+            ))),
+            Err(e) => Err(SolutionError::Parse(e.to_string())),
+        }
+    }
+
+    fn part1(&self, _input: &day{day}::Puzzle) -> Result<Answer, SolutionError> {
+        Err(SolutionError::NotImplemented)
+    }
+
+    fn part2(&self, _input: &day{day}::Puzzle) -> Result<Answer, SolutionError> {
+        Err(SolutionError::NotImplemented)
+    }
+}
+
+#[cfg(test)]
+mod day{day}_tests {
+    use super::*;
+    use crate::aoc_common::file::load_input_lines;
+    use crate::aoc_common::{AocDay, AocYear, DataFileType};
+
+    fn parsed(datafile_type: DataFileType) -> day{day}::Puzzle {
+        let input = load_input_lines(AocYear::Aoc{year}, AocDay::Day{day}, datafile_type)
+            .unwrap_or_else(|e| panic!("{}", e));
+        Day{day}Solution.parse(&input).unwrap()
+    }
+
+    #[test]
+    #[ignore = "paste the sample into Day{day}_DataExample.txt"]
+    fn test_parse_sample() {
+        parsed(DataFileType::SampleData);
+    }
+
+    #[test]
+    #[ignore = "fill in the sample answer"]
+    fn test_part1_with_sample() {
+        let result = Day{day}Solution.part1(&parsed(DataFileType::SampleData));
+        assert_eq!(result, Ok(Answer::Number(0)));
+    }
+
+    #[test]
+    #[ignore = "fill in the sample answer"]
+    fn test_part2_with_sample() {
+        let result = Day{day}Solution.part2(&parsed(DataFileType::SampleData));
+        assert_eq!(result, Ok(Answer::Number(0)));
+    }
+
+    #[test]
+    #[ignore = "fill in the answer"]
+    fn test_part1_with_data() {
+        let result = Day{day}Solution.part1(&parsed(DataFileType::Data));
+        assert_eq!(result, Ok(Answer::Number(0)));
+    }
+
+    #[test]
+    #[ignore = "fill in the answer"]
+    fn test_part2_with_data() {
+        let result = Day{day}Solution.part2(&parsed(DataFileType::Data));
+        assert_eq!(result, Ok(Answer::Number(0)));
+    }
+}
+"#;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ScaffoldError {
+    Exists(PathBuf),
+    Io(PathBuf, String),
+    /// A file the generator edits does not have the expected shape.
+    Malformed(PathBuf, String),
+}
+
+impl fmt::Display for ScaffoldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScaffoldError::Exists(path) => write!(f, "{} already exists", path.display()),
+            ScaffoldError::Io(path, reason) => {
+                write!(f, "could not access {}: {}", path.display(), reason)
+            }
+            ScaffoldError::Malformed(path, reason) => {
+                write!(f, "could not edit {}: {}", path.display(), reason)
+            }
+        }
+    }
+}
+
+/// The generated module for a day.
+pub fn day_module(year: AocYear, day: AocDay) -> String {
+    DAY_TEMPLATE
+        .replace("{year}", &year.to_string())
+        .replace("{day}", &day.to_string())
+}
+
+/// The day of a `pub mod dayN_problems;` or `use crate::aocY::dayN_problems::...` line.
+fn problems_module(text: &str) -> Option<u8> {
+    let rest = text.trim().split("day").nth(1)?;
+    rest.split("_problems").next()?.parse().ok()
+}
+
+/// The year of a `use crate::aocY::...` line.
+fn import_year(text: &str) -> Option<u16> {
+    text.strip_prefix("use crate::aoc")?.get(..4)?.parse().ok()
+}
+
+/// Adds `pub mod dayN_problems;` to the `mod aocY { ... }` block of `main.rs`, adding
+/// the block when the year has no days yet.
+pub fn register_module(main_rs: &str, year: AocYear, day: AocDay) -> Result<String, String> {
+    let mut lines: Vec<String> = main_rs.lines().map(str::to_string).collect();
+    let module = format!("    pub mod day{}_problems;", day);
+    let header = format!("mod aoc{} {{", year);
+    match lines.iter().position(|l| *l == header) {
+        Some(start) => {
+            let end = start
+                + lines[start..]
+                    .iter()
+                    .position(|l| l == "}")
+                    .ok_or(format!("{} is not closed", header))?;
+            if lines[start..end].contains(&module) {
+                return Err(format!("day{}_problems is already declared", day));
+            }
+            let at = (start + 1..end)
+                .find(|&i| problems_module(&lines[i]).is_some_and(|d| d > day.number()))
+                .unwrap_or(end);
+            lines.insert(at, module);
+        }
+        None => {
+            let year_of = |l: &String| {
+                l.strip_prefix("mod aoc")
+                    .and_then(|rest| rest.strip_suffix(" {"))
+                    .and_then(|y| y.parse::<u16>().ok())
+            };
+            let after_last_block = || {
+                let last = lines.iter().rposition(|l| year_of(l).is_some())?;
+                let close = last + lines[last..].iter().position(|l| l == "}")?;
+                Some((close + 2).min(lines.len()))
+            };
+            let at = lines
+                .iter()
+                .position(|l| year_of(l).is_some_and(|y| y > year.number()))
+                .or_else(after_last_block)
+                .unwrap_or(0);
+            let block = [header, module, "}".to_string(), String::new()];
+            lines.splice(at..at, block);
+        }
+    }
+    Ok(lines.join("\n") + "\n")
+}
+
+/// Imports `DayNSolution` in `solution.rs` and adds it to the registry, keeping both
+/// ordered by year then day.
+pub fn register_solution(solution_rs: &str, year: AocYear, day: AocDay) -> Result<String, String> {
+    let mut lines: Vec<String> = solution_rs.lines().map(str::to_string).collect();
+    let key = (year.number(), day.number());
+    let year_of = |l: &str| {
+        l.trim()
+            .strip_prefix("year: AocYear::Aoc")
+            .and_then(|y| y.trim_end_matches(',').parse::<u16>().ok())
+    };
+    let day_of = |l: &str| {
+        l.trim()
+            .strip_prefix("day: AocDay::Day")
+            .and_then(|d| d.trim_end_matches(',').parse::<u8>().ok())
+    };
+
+    // the same day of another year already has a `DayNSolution`
+    let name = format!("Day{}Solution", day);
+    let taken = lines
+        .iter()
+        .any(|l| l.starts_with("use crate::aoc") && l.ends_with(&format!("::{};", name)));
+    let registered = if taken {
+        format!("{}{}", name, year)
+    } else {
+        name.clone()
+    };
+
+    let start = lines
+        .iter()
+        .position(|l| l.starts_with("static REGISTRY"))
+        .ok_or("no REGISTRY")?;
+    let end = start
+        + lines[start..]
+            .iter()
+            .position(|l| l == "];")
+            .ok_or("REGISTRY is not closed")?;
+    let mut at = end;
+    for i in start..end {
+        if let (Some(y), Some(d)) = (year_of(&lines[i]), lines.get(i + 1).and_then(|l| day_of(l))) {
+            if (y, d) == key {
+                return Err(format!("{} day {} is already registered", year, day));
+            }
+            if (y, d) > key {
+                // back up to the `Registration {` line
+                at = i - 1;
+                break;
+            }
+        }
+    }
+    let entry = [
+        "    Registration {".to_string(),
+        format!("        year: AocYear::Aoc{},", year),
+        format!("        day: AocDay::Day{},", day),
+        format!("        solution: &{},", registered),
+        "    },".to_string(),
+    ];
+    lines.splice(at..at, entry);
+
+    let import = if taken {
+        format!(
+            "use crate::aoc{}::day{}_problems::{} as {};",
+            year, day, name, registered
+        )
+    } else {
+        format!("use crate::aoc{}::day{}_problems::{};", year, day, name)
+    };
+    let imports: Vec<(usize, u16)> = (0..lines.len())
+        .filter_map(|i| Some((i, import_year(&lines[i])?)))
+        .collect();
+    let import_at = imports
+        .iter()
+        .copied()
+        .find(|&(i, y)| (y, problems_module(&lines[i]).unwrap_or(0)) > key)
+        .map(|(i, _)| i)
+        .or(imports.last().map(|(i, _)| i + 1))
+        .unwrap_or(0);
+    lines.insert(import_at, import);
+    Ok(lines.join("\n") + "\n")
+}
+
+fn read(path: &Path) -> Result<String, ScaffoldError> {
+    std::fs::read_to_string(path).map_err(|e| ScaffoldError::Io(path.to_path_buf(), e.to_string()))
+}
+
+fn write(path: &Path, contents: &str) -> Result<(), ScaffoldError> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| ScaffoldError::Io(dir.to_path_buf(), e.to_string()))?;
+    }
+    std::fs::write(path, contents).map_err(|e| ScaffoldError::Io(path.to_path_buf(), e.to_string()))
+}
+
+/// Generates the module for a day under `src_dir`, declares it in `main.rs`, registers
+/// the solution and creates empty data files, returns the files written.
+///
+/// Nothing is written when the module already exists or either edit fails.
+pub fn scaffold(src_dir: &Path, year: AocYear, day: AocDay) -> Result<Vec<PathBuf>, ScaffoldError> {
+    let module_path = src_dir
+        .join(format!("aoc{}", year))
+        .join(format!("day{}_problems.rs", day));
+    if module_path.exists() {
+        return Err(ScaffoldError::Exists(module_path));
+    }
+    let main_path = src_dir.join("main.rs");
+    let solution_path = src_dir.join("solution.rs");
+    let main_rs = register_module(&read(&main_path)?, year, day)
+        .map_err(|e| ScaffoldError::Malformed(main_path.clone(), e))?;
+    let solution_rs = register_solution(&read(&solution_path)?, year, day)
+        .map_err(|e| ScaffoldError::Malformed(solution_path.clone(), e))?;
+
+    write(&module_path, &day_module(year, day))?;
+    write(&main_path, &main_rs)?;
+    write(&solution_path, &solution_rs)?;
+    let mut written = vec![module_path, main_path, solution_path];
+    for datafile_type in [DataFileType::Data, DataFileType::SampleData] {
+        let path = src_dir.join(data_file_relative_path(year, day, datafile_type));
+        if !path.exists() {
+            write(&path, "")?;
+            written.push(path);
+        }
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod scaffold_tests {
+    use super::*;
+    use crate::aoc_client::aoc_client_tests::scratch_dir;
+
+    const MAIN_RS: &str = "mod aoc2020 {\n    pub mod day1_problems;\n    pub mod day8_problems;\n}\n\nmod aoc2022 {\n    pub mod day11_problems;\n}\n\nmod cli;\n";
+
+    const SOLUTION_RS: &str = "use crate::aoc2020::day1_problems::Day1Solution;\nuse crate::aoc2020::day8_problems::Day8Solution;\nuse crate::aoc2022::day11_problems::Day11Solution;\nuse crate::aoc_common::{AocDay, AocYear};\n\nstatic REGISTRY: &[Registration] = &[\n    Registration {\n        year: AocYear::Aoc2020,\n        day: AocDay::Day1,\n        solution: &Day1Solution,\n    },\n    Registration {\n        year: AocYear::Aoc2020,\n        day: AocDay::Day8,\n        solution: &Day8Solution,\n    },\n    Registration {\n        year: AocYear::Aoc2022,\n        day: AocDay::Day11,\n        solution: &Day11Solution,\n    },\n];\n";
+
+    #[test]
+    fn test_register_module_keeps_days_ordered() {
+        let main_rs = register_module(MAIN_RS, AocYear::Aoc2020, AocDay::Day5).unwrap();
+        assert!(main_rs.starts_with(
+            "mod aoc2020 {\n    pub mod day1_problems;\n    pub mod day5_problems;\n    pub mod day8_problems;\n}\n"
+        ));
+        let main_rs = register_module(MAIN_RS, AocYear::Aoc2020, AocDay::Day9).unwrap();
+        assert!(main_rs.contains("    pub mod day8_problems;\n    pub mod day9_problems;\n}\n"));
+        assert!(register_module(MAIN_RS, AocYear::Aoc2020, AocDay::Day8).is_err());
+    }
+
+    #[test]
+    fn test_register_module_adds_year_block() {
+        let main_rs = register_module(MAIN_RS, AocYear::Aoc2021, AocDay::Day3).unwrap();
+        assert!(main_rs
+            .contains("}\n\nmod aoc2021 {\n    pub mod day3_problems;\n}\n\nmod aoc2022 {\n"));
+        let main_rs = register_module(MAIN_RS, AocYear::Aoc2023, AocDay::Day1).unwrap();
+        assert!(main_rs.contains("}\n\nmod aoc2023 {\n    pub mod day1_problems;\n}\n\nmod cli;\n"));
+    }
+
+    #[test]
+    fn test_register_solution_keeps_order() {
+        let registered = register_solution(SOLUTION_RS, AocYear::Aoc2021, AocDay::Day3).unwrap();
+        assert!(registered.contains(
+            "use crate::aoc2020::day8_problems::Day8Solution;\nuse crate::aoc2021::day3_problems::Day3Solution;\nuse crate::aoc2022::"
+        ));
+        assert!(registered.contains(
+            "    },\n    Registration {\n        year: AocYear::Aoc2021,\n        day: AocDay::Day3,\n        solution: &Day3Solution,\n    },\n    Registration {\n        year: AocYear::Aoc2022,\n"
+        ));
+        assert!(register_solution(SOLUTION_RS, AocYear::Aoc2020, AocDay::Day8).is_err());
+    }
+
+    #[test]
+    fn test_register_solution_skips_short_imports() {
+        let solution_rs = SOLUTION_RS.replacen(
+            "use crate::aoc2020",
+            "use crate::aoc2;\nuse crate::aoc2020",
+            1,
+        );
+        let registered = register_solution(&solution_rs, AocYear::Aoc2021, AocDay::Day3).unwrap();
+        assert!(registered.starts_with("use crate::aoc2;\n"));
+        assert!(registered.contains(
+            "use crate::aoc2020::day8_problems::Day8Solution;\nuse crate::aoc2021::day3_problems::Day3Solution;\n"
+        ));
+        assert_eq!(import_year("use crate::aoc2é;"), None);
+    }
+
+    #[test]
+    fn test_register_solution_renames_clashing_day() {
+        let registered = register_solution(SOLUTION_RS, AocYear::Aoc2021, AocDay::Day8).unwrap();
+        assert!(registered
+            .contains("use crate::aoc2021::day8_problems::Day8Solution as Day8Solution2021;\n"));
+        assert!(registered.contains("        solution: &Day8Solution2021,\n"));
+    }
+
+    #[test]
+    fn test_scaffold_writes_module_and_data_files() {
+        let dir = scratch_dir("scaffold");
+        std::fs::write(dir.join("main.rs"), MAIN_RS).unwrap();
+        std::fs::write(dir.join("solution.rs"), SOLUTION_RS).unwrap();
+
+        let written = scaffold(&dir, AocYear::Aoc2021, AocDay::Day3).unwrap();
+        let module = dir.join("aoc2021").join("day3_problems.rs");
+        assert_eq!(written.len(), 5);
+        assert_eq!(
+            read(&module).unwrap(),
+            day_module(AocYear::Aoc2021, AocDay::Day3)
+        );
+        assert!(read(&module)
+            .unwrap()
+            .contains("AocDay::Day3, datafile_type"));
+        assert!(read(&dir.join("main.rs"))
+            .unwrap()
+            .contains("mod aoc2021 {"));
+        let data = dir.join("aoc2021").join("data");
+        assert_eq!(read(&data.join("Day3_Data.txt")).unwrap(), "");
+        assert_eq!(read(&data.join("Day3_DataExample.txt")).unwrap(), "");
+
+        assert_eq!(
+            scaffold(&dir, AocYear::Aoc2021, AocDay::Day3),
+            Err(ScaffoldError::Exists(module))
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,230 @@
+use crate::aoc2020::day1_problems::Day1Solution;
+use crate::aoc2020::day5_problems::Day5Solution;
+use crate::aoc2020::day8_problems::Day8Solution;
+use crate::aoc2022::day11_problems::Day11Solution;
+use crate::aoc_common::{AocDay, AocYear};
+use std::any::Any;
+use std::fmt;
+
+/// The answer to one part of a puzzle, as it would be typed into the website.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Answer {
+    Number(i64),
+    Text(String),
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::Number(n) => write!(f, "{}", n),
+            Answer::Text(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+impl From<i32> for Answer {
+    fn from(v: i32) -> Self {
+        Answer::Number(v.into())
+    }
+}
+
+impl From<i64> for Answer {
+    fn from(v: i64) -> Self {
+        Answer::Number(v)
+    }
+}
+
+impl From<u64> for Answer {
+    fn from(v: u64) -> Self {
+        match i64::try_from(v) {
+            Ok(n) => Answer::Number(n),
+            Err(_) => Answer::Text(v.to_string()),
+        }
+    }
+}
+
+impl From<String> for Answer {
+    fn from(v: String) -> Self {
+        Answer::Text(v)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum SolutionError {
+    Parse(String),
+    NoAnswer(String),
+    NotImplemented,
+    /// [`DynSolution::solve_parsed`] was given input parsed by another solution.
+    MismatchedInput,
+}
+
+impl fmt::Display for SolutionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolutionError::Parse(reason) => write!(f, "bad input: {}", reason),
+            SolutionError::NoAnswer(reason) => write!(f, "no answer: {}", reason),
+            SolutionError::NotImplemented => write!(f, "not implemented"),
+            SolutionError::MismatchedInput => write!(f, "input parsed by another solution"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone)]
+pub enum Part {
+    One,
+    Two,
+}
+
+impl TryFrom<u8> for Part {
+    type Error = u8;
+    fn try_from(v: u8) -> Result<Self, u8> {
+        match v {
+            1 => Ok(Part::One),
+            2 => Ok(Part::Two),
+            _ => Err(v),
+        }
+    }
+}
+
+impl fmt::Display for Part {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Part::One => write!(f, "1"),
+            Part::Two => write!(f, "2"),
+        }
+    }
+}
+
+/// A puzzle solution: the input is parsed once and shared by both parts.
+pub trait Solution {
+    type Input: 'static;
+
+    fn parse(&self, input: &str) -> Result<Self::Input, SolutionError>;
+
+    fn part1(&self, input: &Self::Input) -> Result<Answer, SolutionError>;
+
+    fn part2(&self, _input: &Self::Input) -> Result<Answer, SolutionError> {
+        Err(SolutionError::NotImplemented)
+    }
+}
+
+/// Object safe form of [`Solution`] so solutions can be stored in the registry.
+pub trait DynSolution: Sync {
+    fn parse_any(&self, input: &str) -> Result<Box<dyn Any>, SolutionError>;
+
+    fn solve_parsed(&self, part: Part, parsed: &dyn Any) -> Result<Answer, SolutionError>;
+
+    fn solve(&self, part: Part, input: &str) -> Result<Answer, SolutionError> {
+        let parsed = self.parse_any(input)?;
+        self.solve_parsed(part, parsed.as_ref())
+    }
+}
+
+impl<S: Solution + Sync> DynSolution for S {
+    fn parse_any(&self, input: &str) -> Result<Box<dyn Any>, SolutionError> {
+        Ok(Box::new(self.parse(input)?))
+    }
+
+    fn solve_parsed(&self, part: Part, parsed: &dyn Any) -> Result<Answer, SolutionError> {
+        let input = parsed
+            .downcast_ref::<S::Input>()
+            .ok_or(SolutionError::MismatchedInput)?;
+        match part {
+            Part::One => self.part1(input),
+            Part::Two => self.part2(input),
+        }
+    }
+}
+
+pub struct Registration {
+    pub year: AocYear,
+    pub day: AocDay,
+    pub solution: &'static dyn DynSolution,
+}
+
+static REGISTRY: &[Registration] = &[
+    Registration {
+        year: AocYear::Aoc2020,
+        day: AocDay::Day1,
+        solution: &Day1Solution,
+    },
+    Registration {
+        year: AocYear::Aoc2020,
+        day: AocDay::Day5,
+        solution: &Day5Solution,
+    },
+    Registration {
+        year: AocYear::Aoc2020,
+        day: AocDay::Day8,
+        solution: &Day8Solution,
+    },
+    Registration {
+        year: AocYear::Aoc2022,
+        day: AocDay::Day11,
+        solution: &Day11Solution,
+    },
+];
+
+/// All registered solutions, ordered by year then day.
+pub fn registry() -> &'static [Registration] {
+    REGISTRY
+}
+
+pub fn find_solution(year: AocYear, day: AocDay) -> Option<&'static dyn DynSolution> {
+    registry()
+        .iter()
+        .find(|r| r.year == year && r.day == day)
+        .map(|r| r.solution)
+}
+
+#[cfg(test)]
+mod solution_tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_keys_are_unique() {
+        let keys: Vec<_> = registry().iter().map(|r| (r.year, r.day)).collect();
+        for (i, key) in keys.iter().enumerate() {
+            assert!(!keys[i + 1..].contains(key));
+        }
+    }
+
+    #[test]
+    fn test_find_solution() {
+        let day5 = find_solution(AocYear::Aoc2020, AocDay::Day5).unwrap();
+        let result = day5.solve(Part::One, "BFFFBBFRRR\nFFFBBBFRRR\nBBFFBBFRLL");
+        assert_eq!(result, Ok(Answer::Number(820)));
+        assert!(find_solution(AocYear::Aoc2021, AocDay::Day1).is_none());
+    }
+
+    #[test]
+    fn test_parse_once_solve_both_parts() {
+        let day1 = find_solution(AocYear::Aoc2020, AocDay::Day1).unwrap();
+        let parsed = day1.parse_any("1721\n979\n366\n299\n675\n1456").unwrap();
+        assert_eq!(
+            day1.solve_parsed(Part::One, parsed.as_ref()),
+            Ok(Answer::Number(514579))
+        );
+        assert_eq!(
+            day1.solve_parsed(Part::Two, parsed.as_ref()),
+            Ok(Answer::Number(241861950))
+        );
+    }
+
+    #[test]
+    fn test_solve_parsed_rejects_other_input() {
+        let day1 = find_solution(AocYear::Aoc2020, AocDay::Day1).unwrap();
+        let day5 = find_solution(AocYear::Aoc2020, AocDay::Day5).unwrap();
+        let parsed = day5.parse_any("FBFBBFFRLR").unwrap();
+        assert_eq!(
+            day1.solve_parsed(Part::One, parsed.as_ref()),
+            Err(SolutionError::MismatchedInput)
+        );
+    }
+
+    #[test]
+    fn test_answer_from_large_u64() {
+        assert_eq!(Answer::from(7u64), Answer::Number(7));
+        assert_eq!(Answer::from(u64::MAX).to_string(), u64::MAX.to_string());
+    }
+}
@@ -0,0 +1,531 @@
+use crate::aoc_client::{AocClient, ClientError};
+use crate::aoc_common::{AocDay, AocYear};
+use crate::config::Config;
+use crate::solution::{Answer, Part};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Config file key naming the submissions log.
+pub const SUBMISSIONS_FILE_KEY: &str = "submissions_file";
+
+/// What the website said about a submitted answer.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Verdict {
+    Correct,
+    TooHigh { wait: Duration },
+    TooLow { wait: Duration },
+    Wrong { wait: Duration },
+    TooSoon { wait: Duration },
+    AlreadySolved,
+}
+
+impl Verdict {
+    /// How long the website wants us to wait before the next submission.
+    pub fn wait(&self) -> Duration {
+        match self {
+            Verdict::TooHigh { wait }
+            | Verdict::TooLow { wait }
+            | Verdict::Wrong { wait }
+            | Verdict::TooSoon { wait } => *wait,
+            Verdict::Correct | Verdict::AlreadySolved => Duration::ZERO,
+        }
+    }
+
+    pub fn is_wrong(&self) -> bool {
+        matches!(
+            self,
+            Verdict::TooHigh { .. } | Verdict::TooLow { .. } | Verdict::Wrong { .. }
+        )
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Verdict::Correct => "correct",
+            Verdict::TooHigh { .. } => "too-high",
+            Verdict::TooLow { .. } => "too-low",
+            Verdict::Wrong { .. } => "wrong",
+            Verdict::TooSoon { .. } => "too-soon",
+            Verdict::AlreadySolved => "already-solved",
+        }
+    }
+
+    fn from_name(name: &str, wait: Duration) -> Option<Self> {
+        match name {
+            "correct" => Some(Verdict::Correct),
+            "too-high" => Some(Verdict::TooHigh { wait }),
+            "too-low" => Some(Verdict::TooLow { wait }),
+            "wrong" => Some(Verdict::Wrong { wait }),
+            "too-soon" => Some(Verdict::TooSoon { wait }),
+            "already-solved" => Some(Verdict::AlreadySolved),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Verdict::Correct => write!(f, "that's the right answer"),
+            Verdict::TooHigh { .. } => write!(f, "wrong, the answer is too high"),
+            Verdict::TooLow { .. } => write!(f, "wrong, the answer is too low"),
+            Verdict::Wrong { .. } => write!(f, "wrong"),
+            Verdict::TooSoon { wait } => {
+                write!(f, "answered too recently, wait {}s", wait.as_secs())
+            }
+            Verdict::AlreadySolved => write!(f, "this part is already solved"),
+        }
+    }
+}
+
+/// Seconds from "You have 4m 52s left to wait" or "please wait 5 minutes".
+fn parse_wait(page: &str) -> Duration {
+    let page = page.to_lowercase();
+    if let Some((before, _)) = page.split_once(" left to wait") {
+        let wait = before.rsplit("you have ").next().unwrap_or_default();
+        let secs = wait.split_whitespace().fold(0, |total, token| {
+            let (n, unit) = token.split_at(token.len().saturating_sub(1));
+            match (n.parse::<u64>(), unit) {
+                (Ok(n), "h") => total + n * 3600,
+                (Ok(n), "m") => total + n * 60,
+                (Ok(n), "s") => total + n,
+                _ => total,
+            }
+        });
+        return Duration::from_secs(secs);
+    }
+    let minutes = page
+        .split("please wait ")
+        .nth(1)
+        .and_then(|rest| rest.split_whitespace().next())
+        .map(|n| match n {
+            "one" => 1,
+            n => n.parse().unwrap_or(1),
+        });
+    Duration::from_secs(minutes.unwrap_or(0) * 60)
+}
+
+/// Reads the verdict out of the page returned for a submitted answer.
+pub fn parse_verdict(page: &str) -> Option<Verdict> {
+    let wait = parse_wait(page);
+    if page.contains("That's the right answer") {
+        Some(Verdict::Correct)
+    } else if page.contains("That's not the right answer") {
+        if page.contains("your answer is too high") {
+            Some(Verdict::TooHigh { wait })
+        } else if page.contains("your answer is too low") {
+            Some(Verdict::TooLow { wait })
+        } else {
+            Some(Verdict::Wrong { wait })
+        }
+    } else if page.contains("You gave an answer too recently") {
+        Some(Verdict::TooSoon { wait })
+    } else if page.contains("Did you already complete it") {
+        Some(Verdict::AlreadySolved)
+    } else {
+        None
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Submission {
+    pub at: SystemTime,
+    pub year: AocYear,
+    pub day: AocDay,
+    pub part: Part,
+    pub answer: Answer,
+    pub verdict: Verdict,
+}
+
+impl Submission {
+    fn is_for(&self, year: AocYear, day: AocDay, part: Part) -> bool {
+        self.year == year && self.day == day && self.part == part
+    }
+}
+
+impl fmt::Display for Submission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {} {} {} {}",
+            self.at
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            self.year,
+            self.day,
+            self.part,
+            self.verdict.name(),
+            self.verdict.wait().as_secs(),
+            self.answer
+        )
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum SubmitError {
+    Client(ClientError),
+    AlreadySolved { answer: Answer },
+    AlreadySubmitted { answer: Answer, verdict: Verdict },
+    OutOfRange { answer: Answer, reason: String },
+    CoolingDown { wait: Duration },
+    UnknownResponse(String),
+    Log(PathBuf, String),
+}
+
+impl fmt::Display for SubmitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SubmitError::Client(e) => write!(f, "{}", e),
+            SubmitError::AlreadySolved { answer } => {
+                write!(f, "already solved, the answer was {}", answer)
+            }
+            SubmitError::AlreadySubmitted { answer, verdict } => {
+                write!(f, "{} was submitted before: {}", answer, verdict)
+            }
+            SubmitError::OutOfRange { answer, reason } => {
+                write!(f, "not submitting {}: {}", answer, reason)
+            }
+            SubmitError::CoolingDown { wait } => {
+                write!(f, "still cooling down, try again in {}s", wait.as_secs())
+            }
+            SubmitError::UnknownResponse(page) => {
+                write!(f, "could not understand the response: {:.200}", page)
+            }
+            SubmitError::Log(path, reason) => write!(
+                f,
+                "could not access submissions log {}: {}",
+                path.display(),
+                reason
+            ),
+        }
+    }
+}
+
+impl From<ClientError> for SubmitError {
+    fn from(e: ClientError) -> Self {
+        SubmitError::Client(e)
+    }
+}
+
+/// Every answer ever submitted, one
+/// `<unix time> <year> <day> <part> <verdict> <wait secs> <answer>` per line.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SubmissionLog {
+    path: PathBuf,
+    submissions: Vec<Submission>,
+}
+
+fn parse_submission(line: &str) -> Option<Submission> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    match fields[..] {
+        [at, year, day, part, verdict, wait, ref answer @ ..] if !answer.is_empty() => {
+            let wait = Duration::from_secs(wait.parse().ok()?);
+            let answer = answer.join(" ");
+            Some(Submission {
+                at: UNIX_EPOCH + Duration::from_secs(at.parse().ok()?),
+                year: year.parse().ok()?,
+                day: day.parse().ok()?,
+                part: Part::try_from(part.parse::<u8>().ok()?).ok()?,
+                answer: match answer.parse::<i64>() {
+                    Ok(n) => Answer::Number(n),
+                    Err(_) => Answer::Text(answer),
+                },
+                verdict: Verdict::from_name(verdict, wait)?,
+            })
+        }
+        _ => None,
+    }
+}
+
+impl SubmissionLog {
+    /// Loads the log, a missing file is an empty log.
+    pub fn load(path: &Path) -> Result<Self, SubmitError> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(SubmitError::Log(path.to_path_buf(), e.to_string())),
+        };
+        let submissions = text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .enumerate()
+            .map(|(n, line)| {
+                parse_submission(line).ok_or_else(|| {
+                    SubmitError::Log(
+                        path.to_path_buf(),
+                        format!("bad line {}: {:?}", n + 1, line),
+                    )
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            submissions,
+        })
+    }
+
+    pub fn append(&mut self, submission: Submission) -> Result<(), SubmitError> {
+        use std::io::Write;
+        let error = |e: std::io::Error| SubmitError::Log(self.path.clone(), e.to_string());
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(error)?;
+        writeln!(file, "{}", submission).map_err(error)?;
+        self.submissions.push(submission);
+        Ok(())
+    }
+
+    /// Refuses answers that cannot be right judging by earlier verdicts, and any
+    /// submission while the website's cooldown is running.
+    pub fn check(
+        &self,
+        year: AocYear,
+        day: AocDay,
+        part: Part,
+        answer: &Answer,
+        now: SystemTime,
+    ) -> Result<(), SubmitError> {
+        let earlier = self
+            .submissions
+            .iter()
+            .filter(|s| s.is_for(year, day, part));
+        for submission in earlier {
+            match &submission.verdict {
+                Verdict::Correct => {
+                    return Err(SubmitError::AlreadySolved {
+                        answer: submission.answer.clone(),
+                    })
+                }
+                verdict if verdict.is_wrong() && submission.answer == *answer => {
+                    return Err(SubmitError::AlreadySubmitted {
+                        answer: answer.clone(),
+                        verdict: verdict.clone(),
+                    })
+                }
+                _ => {}
+            }
+            let bound = match (&submission.verdict, &submission.answer, answer) {
+                (Verdict::TooHigh { .. }, Answer::Number(high), Answer::Number(n)) if n >= high => {
+                    Some(format!("{} was too high", high))
+                }
+                (Verdict::TooLow { .. }, Answer::Number(low), Answer::Number(n)) if n <= low => {
+                    Some(format!("{} was too low", low))
+                }
+                _ => None,
+            };
+            if let Some(reason) = bound {
+                return Err(SubmitError::OutOfRange {
+                    answer: answer.clone(),
+                    reason,
+                });
+            }
+        }
+        let cooldown_ends = self
+            .submissions
+            .iter()
+            .filter(|s| s.year == year && s.day == day)
+            .map(|s| s.at + s.verdict.wait())
+            .max();
+        match cooldown_ends.map(|end| end.duration_since(now)) {
+            Some(Ok(wait)) if !wait.is_zero() => Err(SubmitError::CoolingDown { wait }),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The submissions log: `submissions_file` in `aoc.toml`, then `submissions.txt` in
+/// the repository.
+pub fn submissions_path(config: &Config) -> PathBuf {
+    config
+        .get_path(SUBMISSIONS_FILE_KEY)
+        .unwrap_or_else(|| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("submissions.txt"))
+}
+
+/// Checks the answer against the log, posts it and logs the verdict.
+pub fn submit(
+    client: &AocClient,
+    log: &mut SubmissionLog,
+    year: AocYear,
+    day: AocDay,
+    part: Part,
+    answer: &Answer,
+    now: SystemTime,
+) -> Result<Verdict, SubmitError> {
+    log.check(year, day, part, answer, now)?;
+    let page = client.post_answer(year, day, part, answer)?;
+    let verdict = parse_verdict(&page).ok_or(SubmitError::UnknownResponse(page))?;
+    log.append(Submission {
+        at: now,
+        year,
+        day,
+        part,
+        answer: answer.clone(),
+        verdict: verdict.clone(),
+    })?;
+    Ok(verdict)
+}
+
+#[cfg(test)]
+mod submit_tests {
+    use super::*;
+    use crate::aoc_client::aoc_client_tests::{scratch_dir, serve};
+
+    const TOO_HIGH: &str = "<article><p>That's not the right answer; your answer is too high.  If you're stuck, make sure you're using the full input data. Please wait one minute before trying again.</p></article>";
+    const TOO_SOON: &str = "<article><p>You gave an answer too recently; you have to wait after submitting an answer before trying again.  You have 4m 52s left to wait.</p></article>";
+    const CORRECT: &str = "<article><p>That's the right answer!  You are one gold star closer to saving your vacation.</p></article>";
+
+    fn at(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    fn log(submissions: Vec<Submission>) -> SubmissionLog {
+        SubmissionLog {
+            path: PathBuf::from("submissions.txt"),
+            submissions,
+        }
+    }
+
+    fn submission(secs: u64, answer: i64, verdict: Verdict) -> Submission {
+        Submission {
+            at: at(secs),
+            year: AocYear::Aoc2020,
+            day: AocDay::Day8,
+            part: Part::One,
+            answer: Answer::Number(answer),
+            verdict,
+        }
+    }
+
+    #[test]
+    fn test_parse_verdicts() {
+        let minute = Duration::from_secs(60);
+        assert_eq!(
+            parse_verdict(TOO_HIGH),
+            Some(Verdict::TooHigh { wait: minute })
+        );
+        assert_eq!(
+            parse_verdict(TOO_SOON),
+            Some(Verdict::TooSoon {
+                wait: Duration::from_secs(292)
+            })
+        );
+        assert_eq!(parse_verdict(CORRECT), Some(Verdict::Correct));
+        assert_eq!(
+            parse_verdict("That's not the right answer.  Because you have guessed incorrectly 5 times on this puzzle, please wait 5 minutes before trying again."),
+            Some(Verdict::Wrong { wait: 5 * minute })
+        );
+        assert_eq!(
+            parse_verdict(
+                "You don't seem to be solving the right level.  Did you already complete it?"
+            ),
+            Some(Verdict::AlreadySolved)
+        );
+        assert_eq!(parse_verdict("<html>maintenance</html>"), None);
+    }
+
+    #[test]
+    fn test_log_round_trip() {
+        let log = log(vec![
+            submission(
+                100,
+                1594,
+                Verdict::TooHigh {
+                    wait: Duration::from_secs(60),
+                },
+            ),
+            submission(200, 1500, Verdict::Correct),
+        ]);
+        let text: String = log.submissions.iter().map(|s| format!("{}\n", s)).collect();
+        assert_eq!(
+            text,
+            "100 2020 8 1 too-high 60 1594\n200 2020 8 1 correct 0 1500\n"
+        );
+        let parsed: Vec<_> = text.lines().map(parse_submission).collect();
+        assert_eq!(
+            parsed,
+            log.submissions.into_iter().map(Some).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_check_refuses_repeats_bounds_and_cooldown() {
+        let wait = Duration::from_secs(60);
+        let log = log(vec![
+            submission(100, 1594, Verdict::TooHigh { wait }),
+            submission(300, 10, Verdict::TooLow { wait }),
+        ]);
+        let check = |answer: i64, now: u64| {
+            log.check(
+                AocYear::Aoc2020,
+                AocDay::Day8,
+                Part::One,
+                &Answer::Number(answer),
+                at(now),
+            )
+        };
+        assert!(matches!(
+            check(1594, 1000),
+            Err(SubmitError::AlreadySubmitted { .. })
+        ));
+        assert!(matches!(
+            check(2000, 1000),
+            Err(SubmitError::OutOfRange { .. })
+        ));
+        assert!(matches!(
+            check(5, 1000),
+            Err(SubmitError::OutOfRange { .. })
+        ));
+        assert_eq!(
+            check(500, 330),
+            Err(SubmitError::CoolingDown {
+                wait: Duration::from_secs(30)
+            })
+        );
+        assert_eq!(check(500, 360), Ok(()));
+    }
+
+    #[test]
+    fn test_submit_posts_and_logs() {
+        let dir = scratch_dir("submit");
+        let log_path = dir.join("submissions.txt");
+        let (base_url, requests) = serve(vec![(200, TOO_HIGH), (200, CORRECT)]);
+        let client = AocClient::new(&base_url, Some("cookie".to_string()));
+        let mut log = SubmissionLog::load(&log_path).unwrap();
+        let mut send = |answer: i64, now: u64| {
+            submit(
+                &client,
+                &mut log,
+                AocYear::Aoc2020,
+                AocDay::Day8,
+                Part::Two,
+                &Answer::Number(answer),
+                at(now),
+            )
+        };
+
+        assert!(matches!(send(900, 1000), Ok(Verdict::TooHigh { .. })));
+        assert!(matches!(
+            send(900, 2000),
+            Err(SubmitError::AlreadySubmitted { .. })
+        ));
+        assert!(matches!(
+            send(800, 1010),
+            Err(SubmitError::CoolingDown { .. })
+        ));
+        assert_eq!(send(800, 2000), Ok(Verdict::Correct));
+        assert!(matches!(
+            send(700, 3000),
+            Err(SubmitError::AlreadySolved { .. })
+        ));
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[0].starts_with("POST /2020/day/8/answer HTTP/1.1"));
+        assert!(requests[0].ends_with("level=2&answer=900"));
+        assert_eq!(SubmissionLog::load(&log_path).unwrap(), log);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
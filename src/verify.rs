@@ -0,0 +1,265 @@
+use crate::answers::{AnswerKey, Answers};
+use crate::aoc_common::file::load_input_lines_from;
+use crate::aoc_common::{AocInputError, DataFileType, InputSearchPath};
+use crate::solution::{registry, Answer, Part, Registration, SolutionError};
+use std::fmt;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Outcome {
+    Pass,
+    Fail,
+    Missing,
+    NoInput,
+    Error(String),
+}
+
+impl Outcome {
+    pub fn is_failure(&self) -> bool {
+        !matches!(self, Outcome::Pass | Outcome::Missing)
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::Pass => write!(f, "pass"),
+            Outcome::Fail => write!(f, "FAIL"),
+            Outcome::Missing => write!(f, "missing"),
+            Outcome::NoInput => write!(f, "no input"),
+            Outcome::Error(_) => write!(f, "ERROR"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct VerifyRow {
+    pub key: AnswerKey,
+    pub expected: Option<Answer>,
+    pub got: Option<Answer>,
+    pub outcome: Outcome,
+}
+
+fn row(key: AnswerKey, expected: Option<Answer>, got: Result<Answer, String>) -> VerifyRow {
+    let (got, outcome) = match got {
+        Ok(got) => {
+            let outcome = match &expected {
+                Some(expected) if *expected == got => Outcome::Pass,
+                Some(_) => Outcome::Fail,
+                None => Outcome::Missing,
+            };
+            (Some(got), outcome)
+        }
+        Err(e) => (None, Outcome::Error(e)),
+    };
+    VerifyRow {
+        key,
+        expected,
+        got,
+        outcome,
+    }
+}
+
+/// Runs every registered solution on its sample and data inputs and compares the
+/// results with the known answers.
+///
+/// Missing or empty inputs, parts that are not implemented and parts without an answer for an
+/// input (e.g. a sample that only covers part 1) are left out unless an answer is stored.
+pub fn verify(search_path: &InputSearchPath, answers: &Answers) -> Vec<VerifyRow> {
+    verify_registrations(registry(), search_path, answers)
+}
+
+fn verify_registrations(
+    registrations: &[Registration],
+    search_path: &InputSearchPath,
+    answers: &Answers,
+) -> Vec<VerifyRow> {
+    let mut rows = Vec::new();
+    for registration in registrations {
+        for datafile_type in [DataFileType::SampleData, DataFileType::Data] {
+            let key = |part| AnswerKey {
+                year: registration.year,
+                day: registration.day,
+                part,
+                datafile_type,
+            };
+            let parts = [Part::One, Part::Two];
+            let input = load_input_lines_from(
+                search_path,
+                registration.year,
+                registration.day,
+                datafile_type,
+            );
+            let input = match input {
+                Ok(input) => input,
+                Err(AocInputError::NotFound { .. } | AocInputError::Empty { .. }) => {
+                    for part in parts {
+                        if let Some(expected) = answers.get(&key(part)) {
+                            rows.push(VerifyRow {
+                                key: key(part),
+                                expected: Some(expected.clone()),
+                                got: None,
+                                outcome: Outcome::NoInput,
+                            });
+                        }
+                    }
+                    continue;
+                }
+                Err(e) => {
+                    for part in parts {
+                        rows.push(row(
+                            key(part),
+                            answers.get(&key(part)).cloned(),
+                            Err(e.to_string()),
+                        ));
+                    }
+                    continue;
+                }
+            };
+            let parsed = registration.solution.parse_any(&input);
+            for part in parts {
+                let expected = answers.get(&key(part)).cloned();
+                let got = match &parsed {
+                    Ok(parsed) => registration.solution.solve_parsed(part, parsed.as_ref()),
+                    Err(e) => Err(e.clone()),
+                };
+                match got {
+                    Err(SolutionError::NotImplemented | SolutionError::NoAnswer(_))
+                        if expected.is_none() => {}
+                    got => rows.push(row(key(part), expected, got.map_err(|e| e.to_string()))),
+                }
+            }
+        }
+    }
+    rows
+}
+
+/// Stores the produced answer of every `missing` row, returns how many were added.
+pub fn record_missing(rows: &[VerifyRow], answers: &mut Answers) -> usize {
+    let mut recorded = 0;
+    for row in rows {
+        if let (Outcome::Missing, Some(got)) = (&row.outcome, &row.got) {
+            answers.insert(row.key, got.clone());
+            recorded += 1;
+        }
+    }
+    recorded
+}
+
+pub fn format_table(rows: &[VerifyRow]) -> String {
+    let show = |answer: &Option<Answer>| answer.as_ref().map_or("-".to_string(), |a| a.to_string());
+    let mut table = format!(
+        "{:<4} {:>3} {:>4} {:<6} {:<8} {:>16} {:>16}\n",
+        "year", "day", "part", "input", "status", "expected", "got"
+    );
+    for row in rows {
+        table.push_str(&format!(
+            "{:<4} {:>3} {:>4} {:<6} {:<8} {:>16} {:>16}",
+            row.key.year.to_string(),
+            row.key.day.to_string(),
+            row.key.part.to_string(),
+            row.key.datafile_type.to_string(),
+            row.outcome.to_string(),
+            show(&row.expected),
+            show(&row.got)
+        ));
+        if let Outcome::Error(e) = &row.outcome {
+            table.push_str(&format!("  {}", e));
+        }
+        table.push('\n');
+    }
+    let count = |f: fn(&Outcome) -> bool| rows.iter().filter(|r| f(&r.outcome)).count();
+    table.push_str(&format!(
+        "{} passed, {} failed, {} missing",
+        count(|o| *o == Outcome::Pass),
+        count(Outcome::is_failure),
+        count(|o| *o == Outcome::Missing)
+    ));
+    table
+}
+
+#[cfg(test)]
+mod verify_tests {
+    use super::*;
+    use crate::answers::answers_path;
+    use crate::aoc_common::{AocDay, AocYear};
+    use crate::config::Config;
+
+    fn builtin() -> InputSearchPath {
+        InputSearchPath::new(None, None, &Config::default())
+    }
+
+    fn key(day: AocDay, part: Part, datafile_type: DataFileType) -> AnswerKey {
+        AnswerKey {
+            year: AocYear::Aoc2020,
+            day,
+            part,
+            datafile_type,
+        }
+    }
+
+    #[test]
+    fn test_repository_answers_pass() {
+        let answers = Answers::load(&answers_path(None, &Config::default())).unwrap();
+        let rows = verify(&builtin(), &answers);
+        let failures: Vec<_> = rows.iter().filter(|r| r.outcome.is_failure()).collect();
+        assert!(failures.is_empty(), "{}", format_table(&rows));
+        assert!(rows.iter().any(|r| r.outcome == Outcome::Pass));
+    }
+
+    #[test]
+    fn test_wrong_and_missing_answers() {
+        let mut answers = Answers::default();
+        answers.insert(
+            key(AocDay::Day5, Part::One, DataFileType::Data),
+            Answer::Number(1),
+        );
+        let rows = verify(&builtin(), &answers);
+        let day5 = |part| {
+            rows.iter()
+                .find(|r| r.key == key(AocDay::Day5, part, DataFileType::Data))
+                .unwrap()
+        };
+        assert_eq!(day5(Part::One).outcome, Outcome::Fail);
+        assert_eq!(day5(Part::Two).outcome, Outcome::Missing);
+        assert_eq!(day5(Part::Two).got, Some(Answer::Number(676)));
+    }
+
+    #[test]
+    fn test_record_missing_only_adds_missing() {
+        let mut answers = Answers::default();
+        let expected = key(AocDay::Day5, Part::One, DataFileType::Data);
+        answers.insert(expected, Answer::Number(1));
+        let rows = verify(&builtin(), &answers);
+        let recorded = record_missing(&rows, &mut answers);
+        assert!(recorded > 0);
+        assert_eq!(answers.get(&expected), Some(&Answer::Number(1)));
+        assert_eq!(
+            answers.get(&key(AocDay::Day5, Part::Two, DataFileType::Data)),
+            Some(&Answer::Number(676))
+        );
+    }
+
+    #[test]
+    fn test_stored_answer_without_input_fails() {
+        use crate::aoc2020::day5_problems::Day5Solution;
+
+        let registrations = [Registration {
+            year: AocYear::Aoc2015,
+            day: AocDay::Day25,
+            solution: &Day5Solution,
+        }];
+        let stored = AnswerKey {
+            year: AocYear::Aoc2015,
+            day: AocDay::Day25,
+            part: Part::One,
+            datafile_type: DataFileType::Data,
+        };
+        let mut answers = Answers::default();
+        answers.insert(stored, Answer::Number(1));
+        let rows = verify_registrations(&registrations, &builtin(), &answers);
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].key, stored);
+        assert_eq!(rows[0].outcome, Outcome::NoInput);
+        assert!(rows[0].outcome.is_failure());
+    }
+}